@@ -52,17 +52,15 @@ fn test_track() -> MeasureTrack<DrumsetAction, Drumset> {
     track.pause(WHOLE);
     track.measure().unwrap();
 
-    for _ in 0..2 {
-        notes!(track, EIGTH, Bass);
-        notes!(track, EIGTH, HiHat);
-        notes!(track, EIGTH, Bass, Snare);
-        notes!(track, EIGTH, HiHat);
-        notes!(track, EIGTH, Bass);
-        notes!(track, EIGTH, HiHat);
-        notes!(track, EIGTH, Bass, Snare);
-        notes!(track, EIGTH, HiHat);
-        track.measure().unwrap();
-    }
+    // Bass and snare with hi-hats on the offbeats, written as a step pattern
+    // with one character per eighth
+    let beat = StepPattern::new(EIGTH)
+        .add_lane(HiHat, ".x.x.x.x")
+        .add_lane(Snare, "..x...x.")
+        .add_lane(Bass,  "x.x.x.x.");
+
+    beat.repeat(2).place(&mut track).unwrap();
+    track.measure().unwrap();
 
     return track;
 }
@@ -7,7 +7,7 @@ use synth_music::prelude::*;
 use std::time::Duration;
 
 // Specify possible drumset actions
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum DrumsetAction {
     Bass,
     Snare,
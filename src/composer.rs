@@ -2,15 +2,34 @@ pub mod note;
 pub mod music_key;
 pub mod unbound_track;
 pub mod measure_track;
+mod note_state;
 pub mod time_signature;
+pub mod parameter;
+pub mod dynamics;
+pub mod groove;
+pub mod humanize;
+pub mod arpeggiator;
+pub mod step_sequencer;
 
 use crate::instrument::Instrument;
 
 #[doc(inline)]
-pub use note::{Note, length, Length, ScaledValue};
+pub use note::{Note, length, Length, ScaledValue, Articulation};
 #[doc(inline)]
 pub use time_signature::TimeSignature;
 #[doc(inline)]
+pub use parameter::{Parameter, ParameterMap};
+#[doc(inline)]
+pub use dynamics::{DynamicsCurve, Dynamic, Accent, DynamicsTable};
+#[doc(inline)]
+pub use groove::{Groove, GrooveStep};
+#[doc(inline)]
+pub use humanize::Humanize;
+#[doc(inline)]
+pub use arpeggiator::{Arpeggiator, ArpeggioPattern};
+#[doc(inline)]
+pub use step_sequencer::StepPattern;
+#[doc(inline)]
 pub use music_key::{MusicKey, KeyTonic, KeyType};
 #[doc(inline)]
 pub use unbound_track::UnboundTrack;
@@ -33,11 +52,14 @@ pub use crate::file_export::export_info::{ExportTrack, SectionInfo, Tone};
 /// and are called by the Track macros like `sequential_notes!` or `notes!`.
 /// They also have to return a mutable reference to the placed note.
 /// 
-/// ## start_dynamic_change, end_dynamic_change
+/// ## start_dynamic_change, start_dynamic_change_with, end_dynamic_change
 /// 
 /// Mark the start and end of a dynamics change. The dynamics must interpolate
 /// over the marked region, going from the currently active intensity to the
-/// intensity specified in end_dynamic_change.
+/// intensity specified in end_dynamic_change. start_dynamic_change changes
+/// the intensity linearly, while start_dynamic_change_with takes the shape of
+/// the change as a `DynamicsCurve`. The default implementation of
+/// start_dynamic_change_with ignores the curve and changes linearly.
 /// 
 /// ## set_intensity, set_play_fraction
 /// 
@@ -45,12 +67,52 @@ pub use crate::file_export::export_info::{ExportTrack, SectionInfo, Tone};
 /// placed after the function call are affected; all notes that are already
 /// placed remain unchanged.
 /// 
+/// ## set_dynamic
+/// 
+/// Set the intensity with a named `Dynamic` marking, which is looked up in the
+/// track's `DynamicsTable`. `Sfz` and `Fp` only affect the next note placed,
+/// see `Dynamic` for details. The default implementation sets the intensity
+/// from the default `DynamicsTable`, and ignores the accent of `Sfz` and the
+/// forte attack of `Fp`.
+/// 
+/// ## set_parameter, start_parameter_change, end_parameter_change
+/// 
+/// The same as the intensity functions, but for an instrument `Parameter`.
+/// Parameter changes are interpolated over the marked region in the same way as
+/// dynamics changes. Changes of different parameters may overlap. The default
+/// implementations ignore parameters, for tracks whose instruments don't read
+/// any.
+/// 
+/// ## start_slur, end_slur
+/// 
+/// Mark the start and end of a slur. All notes placed in between are played
+/// legato, so every note overlaps with the next one. Except for the first one,
+/// the notes are also marked as `slurred`, so instruments can glide from the
+/// previous note. The default implementations ignore slurs.
+/// 
+/// ## set_instrument
+/// 
+/// Change the instrument for all notes placed after the function call. Notes
+/// that are already placed keep the instrument they were placed with. This is
+/// also how instrument parameters can be changed within a track. To switch
+/// between different instrument types, use an enum created with
+/// `instrument_set!`. The default implementation ignores the change, for
+/// tracks that only support a single instrument.
+/// 
 /// ## get_active_note
 /// 
 /// Retrieves a mutable reference to the most recent note placed on the track.
 /// The return value is wrapped in an Option in case there are no notes placed.
 /// 
 /// 
+/// ## remaining_measure_length, end_measure
+/// 
+/// Used by generators like `Arpeggiator` that place many notes at once and
+/// need to respect measure boundaries. `remaining_measure_length` returns the
+/// length left in the active measure, and `end_measure` completes it. Tracks
+/// without measures use the default implementations, which return `None` and
+/// do nothing.
+/// 
 /// ## convert_to_export_track
 /// 
 /// Usually called by the render macros / functions. An export track is an
@@ -69,13 +131,43 @@ where
     fn notes(&mut self, length: Length, values: Vec<T>) -> &mut Note<T>;
 
     fn start_dynamic_change(&mut self);
+    fn start_dynamic_change_with(&mut self, _curve: DynamicsCurve) {
+        self.start_dynamic_change();
+    }
     fn end_dynamic_change(&mut self, intensity: f32);
 
     fn set_intensity(&mut self, intensity: f32);
+    fn set_dynamic(&mut self, dynamic: Dynamic) {
+        let dynamic = match dynamic {
+            Dynamic::Fp => Dynamic::P,
+            dynamic => dynamic,
+        };
+
+        if let Some(intensity) = DynamicsTable::new().intensity(dynamic) {
+            self.set_intensity(intensity);
+        }
+    }
     fn set_play_fraction(&mut self, play_fraction: f32);
 
+    fn set_parameter(&mut self, _key: Parameter, _value: f32) {}
+    fn start_parameter_change(&mut self, _key: Parameter) {}
+    fn end_parameter_change(&mut self, _key: Parameter, _value: f32) {}
+
+    fn start_slur(&mut self) {}
+    fn end_slur(&mut self) {}
+
+    fn set_instrument(&mut self, _instrument: U) {}
+
     fn get_active_note(&mut self) -> Option<&mut Note<T>>;
 
+    fn remaining_measure_length(&self) -> Option<Length> {
+        None
+    }
+
+    fn end_measure(&mut self) -> Result<(), &str> {
+        Ok(())
+    }
+
     fn convert_to_export_track(&self, section_info: SectionInfo) -> ExportTrack<U>;
 }
 
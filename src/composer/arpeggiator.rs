@@ -0,0 +1,224 @@
+use super::{MusicTrack, ScaledValue, Length, Note, MusicKey};
+use super::note::length::ZERO;
+use crate::instrument::Instrument;
+use crate::instrument::combinators::Transpose;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// The order in which an `Arpeggiator` plays the tones of a chord.
+/// 
+/// The tones are all chord tones over all octaves of the arpeggio. A pattern
+/// repeats until the duration is filled.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum ArpeggioPattern {
+    /// From the lowest to the highest tone.
+    #[default]
+    Up,
+    /// From the highest to the lowest tone.
+    Down,
+    /// Up and back down, without repeating the highest and lowest tone.
+    UpDown,
+    /// A random tone on every step.
+    Random,
+    /// The tones in the order they are given in the chord, repeated for every
+    /// octave.
+    AsPlayed,
+    /// Indices into the tones sorted from low to high. Indices beyond the
+    /// amount of tones wrap around.
+    Custom(Vec<usize>),
+}
+
+/// Expands a chord into a sequence of single notes of the same length, and
+/// places them on a track.
+/// 
+/// The arpeggio spans a number of octaves, where the chord is repeated an
+/// octave higher for every additional octave. The tones are sorted by their
+/// pitch in the key that is given, which should be the key of the section, so
+/// scale degrees and absolute tones can be mixed. On a `MeasureTrack`, measures
+/// that are filled are completed automatically when more notes follow, and
+/// notes that cross a measure boundary are split into tied notes. The last
+/// measure is left open, so the track can continue as usual.
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+/// 
+/// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+/// 
+/// let arpeggio = Arpeggiator::new(vec![first(3), third(3), fifth(3)], SIXTEENTH, music_key::C_MAJOR)
+///     .set_pattern(ArpeggioPattern::UpDown)
+///     .set_octaves(2);
+/// 
+/// // Two measures of sixteenth notes
+/// arpeggio.fill(&mut track, WHOLE * 2).unwrap();
+/// track.measure().unwrap();
+/// 
+/// // Works with chords as well
+/// let chord = Chord::symbol("Am7", 3).unwrap();
+/// Arpeggiator::new(chord.tones(), EIGTH.triole(), music_key::C_MAJOR)
+///     .set_pattern(ArpeggioPattern::Random)
+///     .set_seed(7)
+///     .fill(&mut track, WHOLE)
+///     .unwrap();
+/// track.measure().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Arpeggiator<T: ScaledValue> {
+    chord: Vec<T>,
+    pattern: ArpeggioPattern,
+    octaves: u32,
+    step: Length,
+    key: MusicKey,
+    seed: u64,
+}
+
+impl<T> Arpeggiator<T>
+where
+    T: ScaledValue + Transpose,
+    T::ConcreteValue: Ord,
+{
+    /// Create an arpeggiator playing the chord upwards in one octave, with
+    /// notes of length `step`. The tones are sorted by their pitch in `key`.
+    pub fn new(chord: Vec<T>, step: Length, key: MusicKey) -> Self {
+        Self {
+            chord,
+            pattern: ArpeggioPattern::Up,
+            octaves: 1,
+            step,
+            key,
+            seed: 0,
+        }
+    }
+
+    pub fn set_pattern(mut self, pattern: ArpeggioPattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Set the amount of octaves the arpeggio spans.
+    pub fn set_octaves(mut self, octaves: u32) -> Self {
+        self.octaves = u32::max(octaves, 1);
+        self
+    }
+
+    /// Set the seed for `ArpeggioPattern::Random`.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The tones over all octaves in the order given by the chord.
+    fn tones(&self) -> Vec<T> {
+        (0..self.octaves as i32)
+            .flat_map(|octave| self.chord.iter().map(move |tone| tone.transpose(octave * 12)))
+            .collect()
+    }
+
+    fn sorted_tones(&self) -> Vec<T> {
+        let mut tones = self.tones();
+        tones.sort_by_key(|tone| tone.to_concrete_value(self.key));
+        tones
+    }
+
+    /// A single repetition of the pattern. `Random` is generated per step
+    /// instead.
+    fn sequence(&self) -> Vec<T> {
+        let sorted = self.sorted_tones();
+
+        match &self.pattern {
+            ArpeggioPattern::Up | ArpeggioPattern::Random => sorted,
+            ArpeggioPattern::Down => sorted.into_iter().rev().collect(),
+            ArpeggioPattern::UpDown => {
+                let inner = sorted.len().saturating_sub(2);
+                let down: Vec<T> = sorted.iter().rev().skip(1).take(inner).cloned().collect();
+                [sorted, down].concat()
+            }
+            ArpeggioPattern::AsPlayed => self.tones(),
+            ArpeggioPattern::Custom(indices) => {
+                if sorted.is_empty() {
+                    return sorted;
+                }
+
+                indices.iter()
+                    .map(|index| sorted[index % sorted.len()])
+                    .collect()
+            }
+        }
+    }
+
+    /// Place the arpeggio on a track until `duration` is filled. The last note
+    /// is shortened if the duration isn't a multiple of the step length. An
+    /// error is returned if a measure can't be completed.
+    pub fn fill<U, M>(&self, track: &mut M, duration: Length) -> Result<(), &'static str>
+    where
+        U: Instrument<ConcreteValue = T::ConcreteValue>,
+        M: MusicTrack<T, U>,
+    {
+        let sequence = self.sequence();
+        if sequence.is_empty() || self.step == ZERO {
+            return Err("Empty arpeggio");
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut remaining = duration;
+        let mut step = 0;
+
+        while remaining > ZERO {
+            let value = match self.pattern {
+                ArpeggioPattern::Random => sequence[rng.gen_range(0..sequence.len())],
+                _ => sequence[step % sequence.len()],
+            };
+
+            let length = Length::min(self.step, remaining);
+            place_split(track, length, vec![value], |_| ())?;
+
+            remaining -= length;
+            step += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Place a note, which is split into tied notes at measure boundaries. Every
+/// part of the note is passed to `edit`. Full measures are completed before
+/// the note is placed.
+pub(super) fn place_split<T, U, M, F>(
+    track: &mut M,
+    length: Length,
+    values: Vec<T>,
+    mut edit: F,
+) -> Result<(), &'static str>
+where
+    T: ScaledValue,
+    U: Instrument<ConcreteValue = T::ConcreteValue>,
+    M: MusicTrack<T, U>,
+    F: FnMut(&mut Note<T>),
+{
+    let mut length = length;
+
+    while let Some(space) = track.remaining_measure_length() {
+        if space == ZERO {
+            track.end_measure().map_err(|_| "Invalid measure bounds")?;
+            continue;
+        }
+
+        if length <= space {
+            break;
+        }
+
+        let note = track.notes(space, values.clone());
+        // Pauses are split without ties
+        note.tied = !note.values.is_empty();
+        edit(note);
+
+        length -= space;
+    }
+
+    edit(track.notes(length, values));
+    Ok(())
+}
+
+mod tests;
@@ -0,0 +1,154 @@
+#![cfg(test)]
+
+use crate::prelude::*;
+use crate::prelude::tet12::*;
+use crate::prelude::length::*;
+use crate::prelude::predefined::SineGenerator as instrument;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 8000,
+};
+
+const INFO: SectionInfo = SectionInfo {
+    bpm: 120.0,
+    key: music_key::C_MAJOR,
+    settings: &SETTINGS,
+};
+
+fn triad() -> Vec<TET12ScaledTone> {
+    vec![first(4), third(4), fifth(4)]
+}
+
+fn tone_values<M: MusicTrack<TET12ScaledTone, predefined::SineGenerator>>(track: &M) -> Vec<Vec<i32>> {
+    track.convert_to_export_track(INFO).tones.iter()
+        .map(|tone| tone.concrete_values.iter().map(|value| value.0).collect())
+        .collect()
+}
+
+#[test]
+fn patterns() {
+    let arpeggio = |pattern| Arpeggiator::new(triad(), EIGTH, INFO.key).set_pattern(pattern).sequence();
+
+    assert_eq!(arpeggio(ArpeggioPattern::Up), vec![first(4), third(4), fifth(4)]);
+    assert_eq!(arpeggio(ArpeggioPattern::Down), vec![fifth(4), third(4), first(4)]);
+    assert_eq!(arpeggio(ArpeggioPattern::UpDown), vec![first(4), third(4), fifth(4), third(4)]);
+    assert_eq!(arpeggio(ArpeggioPattern::Custom(vec![0, 2, 1, 5])), vec![first(4), fifth(4), third(4), fifth(4)]);
+}
+
+#[test]
+fn as_played_keeps_order() {
+    let chord = vec![fifth(4), first(4), third(4)];
+
+    let as_played = Arpeggiator::new(chord.clone(), EIGTH, INFO.key).set_pattern(ArpeggioPattern::AsPlayed);
+    let up = Arpeggiator::new(chord, EIGTH, INFO.key).set_pattern(ArpeggioPattern::Up);
+
+    assert_eq!(as_played.sequence(), vec![fifth(4), first(4), third(4)]);
+    assert_eq!(up.sequence(), vec![first(4), third(4), fifth(4)]);
+}
+
+#[test]
+fn mixed_absolute_and_relative_tones() {
+    let chord = vec![absolute::c(5), first(3), absolute::c(3)];
+    let arpeggio = Arpeggiator::new(chord, QUARTER, INFO.key);
+
+    assert_eq!(arpeggio.sequence(), vec![first(3), absolute::c(3), absolute::c(5)]);
+
+    // The seventh of D major is C#5
+    let d_major = MusicKey { tonic: KeyTonic::D, key_type: KeyType::Major };
+    let chord = vec![seventh(4), absolute::c(5), first(4)];
+    let arpeggio = Arpeggiator::new(chord, QUARTER, d_major);
+
+    assert_eq!(arpeggio.sequence(), vec![first(4), absolute::c(5), seventh(4)]);
+}
+
+#[test]
+fn degrees_of_custom_scale() {
+    const OCTATONIC: KeyType = KeyType::Custom(&[0, 1, 3, 4, 6, 7, 9, 10]);
+    let key = MusicKey { tonic: KeyTonic::C, key_type: OCTATONIC };
+
+    // The eighth degree is A#4, below B4
+    let chord = vec![absolute::b(4), degree(8, 4), degree(9, 3)];
+    let arpeggio = Arpeggiator::new(chord, QUARTER, key);
+
+    assert_eq!(arpeggio.sequence(), vec![degree(9, 3), degree(8, 4), absolute::b(4)]);
+}
+
+#[test]
+fn octaves() {
+    let arpeggio = Arpeggiator::new(triad(), EIGTH, INFO.key)
+        .set_pattern(ArpeggioPattern::UpDown)
+        .set_octaves(2);
+
+    let values: Vec<i32> = arpeggio.sequence().iter()
+        .map(|tone| tone.to_concrete_value(music_key::C_MAJOR).0)
+        .collect();
+
+    assert_eq!(values, vec![-9, -5, -2, 3, 7, 10, 7, 3, -2, -5]);
+}
+
+#[test]
+fn fill_unbound_track() {
+    let mut track = UnboundTrack::new(instrument);
+    Arpeggiator::new(triad(), QUARTER, INFO.key).fill(&mut track, WHOLE + EIGTH).unwrap();
+
+    // C E G C, and a shortened E
+    assert_eq!(tone_values(&track), vec![vec![-9], vec![-5], vec![-2], vec![-9], vec![-5]]);
+}
+
+#[test]
+fn fill_splits_at_measure_boundaries() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_play_fraction(1.0);
+
+    // The third note crosses the first bar line
+    Arpeggiator::new(triad(), QUARTER.dot(), INFO.key).fill(&mut track, WHOLE * 2).unwrap();
+    track.measure().unwrap();
+
+    let export = track.convert_to_export_track(INFO);
+    assert_eq!(export.tones.len(), 6);
+
+    // The tied halves are merged into one tone of a dotted quarter (0.75s)
+    let third_tone = &export.tones[2];
+    assert_eq!(third_tone.concrete_values[0].0, -2);
+    assert!((third_tone.play_duration.as_secs_f32() - 0.75).abs() < 1e-3);
+
+    // C E G C E and a shortened G
+    assert_eq!(tone_values(&track).last(), Some(&vec![-2]));
+}
+
+#[test]
+fn fill_continues_started_measure() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(3, 4));
+
+    track.note(HALF, first(3));
+    Arpeggiator::new(triad(), EIGTH, INFO.key).fill(&mut track, HALF).unwrap();
+    track.note(HALF, first(3));
+    track.measure().unwrap();
+
+    assert_eq!(tone_values(&track).len(), 6);
+}
+
+#[test]
+fn random_is_deterministic() {
+    let fill = |seed| {
+        let mut track = UnboundTrack::new(instrument);
+        Arpeggiator::new(triad(), SIXTEENTH, INFO.key)
+            .set_pattern(ArpeggioPattern::Random)
+            .set_seed(seed)
+            .fill(&mut track, WHOLE)
+            .unwrap();
+        tone_values(&track)
+    };
+
+    assert_eq!(fill(1), fill(1));
+    assert_ne!(fill(1), fill(2));
+    assert!(fill(1).iter().all(|values| [-9, -5, -2].contains(&values[0])));
+}
+
+#[test]
+fn empty_chord() {
+    let mut track = UnboundTrack::new(instrument);
+    let arpeggio: Arpeggiator<TET12ScaledTone> = Arpeggiator::new(vec![], EIGTH, INFO.key);
+
+    assert!(arpeggio.fill(&mut track, WHOLE).is_err());
+}
@@ -0,0 +1,299 @@
+use crate::instrument::curve::{Curve, ScaleType};
+use crate::instrument::effects::db_to_amplitude;
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Intensities are clamped to this (-60dB) before interpolating logarithmically
+const MIN_LOG_INTENSITY: f32 = 0.001;
+
+/// The time a fortepiano note takes to fall from forte to piano.
+pub const FORTEPIANO_DROP: Duration = Duration::from_millis(100);
+
+/// The shape of a dynamics change (crescendo or diminuendo), passed to
+/// `start_dynamic_change_with()`.
+///
+/// Loudness is perceived logarithmically, so a linear change in amplitude
+/// sounds like most of the change happens at the quiet end. Use `Logarithmic`
+/// for a change that sounds even.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+///
+/// let mut track = UnboundTrack::new(predefined::SineGenerator);
+/// track.set_intensity(0.1);
+///
+/// track.start_dynamic_change_with(DynamicsCurve::Logarithmic);
+/// sequential_notes!(track, QUARTER, first(4), second(4), third(4));
+/// track.end_dynamic_change(0.9);
+///
+/// // Any `Curve` can be used, which maps the progress of the change (x) from
+/// // 0 to 1 to the progress of the intensity (y) from 0 to 1.
+/// let curve = LinearCurve::new()
+///     .add_point(0.0, 0.0)
+///     .add_point(0.8, 0.2)
+///     .add_point(1.0, 1.0);
+///
+/// track.start_dynamic_change_with(DynamicsCurve::custom(curve));
+/// sequential_notes!(track, QUARTER, fourth(4), fifth(4), sixth(4));
+/// track.end_dynamic_change(0.1);
+/// ```
+#[derive(Clone, Default)]
+pub enum DynamicsCurve {
+    /// Change the amplitude linearly.
+    #[default]
+    Linear,
+    /// Change the amplitude in even steps on a logarithmic (dB) scale, which
+    /// sounds like an even change.
+    Logarithmic,
+    /// Most of the change happens at the start, then it slowly approaches the
+    /// target intensity. This is the mirrored shape of `Logarithmic`.
+    Exponential,
+    /// Start and end the change slowly, with most of the change in the middle.
+    SCurve,
+    /// A custom shape, see `DynamicsCurve::custom()`.
+    Custom(Arc<dyn Curve + Send + Sync>),
+}
+
+/// Describes the part of a dynamics change that a single `Tone` covers.
+///
+/// `intensity` is the intensity at the start and end of the whole change, and
+/// `position` is where the tone starts and ends within the change, from `0.0`
+/// to `1.0`. A position beyond `1.0` means the tone continues after the
+/// change, and keeps the end intensity.
+#[derive(Clone)]
+pub struct DynamicsChange {
+    pub curve: DynamicsCurve,
+    pub intensity: Range<f32>,
+    pub position: Range<f32>,
+}
+
+/// Named dynamic markings as used in traditional music notation. The
+/// intensity of every level is looked up in a `DynamicsTable`.
+///
+/// Most markings set the intensity for all following notes. There are two
+/// exceptions that only affect the next note placed:
+/// - `Sfz` (sforzando) gives the next note an `Accent::Sforzando`.
+/// - `Fp` (fortepiano) starts the next note forte and lets it fall to piano
+///   within `FORTEPIANO_DROP`. The notes after that are piano.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+///
+/// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+///
+/// track.set_dynamic(Dynamic::Mp);
+/// track.note(QUARTER, first(4));
+/// track.note(QUARTER, second(4)).accent();
+/// track.note(QUARTER, third(4));
+/// track.note(QUARTER, fourth(4)).marcato();
+/// track.measure().unwrap();
+///
+/// track.set_dynamic(Dynamic::Fp);
+/// track.note(WHOLE, fifth(4));
+/// track.measure().unwrap();
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dynamic {
+    /// Pianississimo
+    Ppp,
+    /// Pianissimo
+    Pp,
+    /// Piano
+    P,
+    /// Mezzo-piano
+    Mp,
+    /// Mezzo-forte
+    Mf,
+    /// Forte
+    F,
+    /// Fortissimo
+    Ff,
+    /// Fortississimo
+    Fff,
+    /// Sforzando
+    Sfz,
+    /// Fortepiano
+    Fp,
+}
+
+/// Accents that make a single note louder. They are applied on top of the
+/// beat emphasis of the `TimeSignature`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Accent {
+    Accent,
+    Marcato,
+    Sforzando,
+}
+
+/// Maps dynamic markings to intensities, and accents to intensity factors.
+///
+/// Every track has a table, which can be replaced with `set_dynamics_table()`.
+/// Use the same table for all tracks of a composition, so that the markings
+/// mean the same everywhere.
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// let table = DynamicsTable::new()
+///     .set_db(Dynamic::Fff, 0.0)
+///     .set_db(Dynamic::Ppp, -40.0)
+///     .set_accent(Accent::Marcato, 1.8);
+///
+/// let mut track: UnboundTrack<TET12ScaledTone, _> = UnboundTrack::new(predefined::SineGenerator);
+/// track.set_dynamics_table(table);
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DynamicsTable {
+    levels: [f32; 8],
+    accents: [f32; 3],
+}
+
+impl Dynamic {
+    /// The index into the levels of a `DynamicsTable`. `Sfz` uses the accent
+    /// instead, and `Fp` starts at forte.
+    fn level_index(self) -> Option<usize> {
+        match self {
+            Self::Ppp => Some(0),
+            Self::Pp  => Some(1),
+            Self::P   => Some(2),
+            Self::Mp  => Some(3),
+            Self::Mf  => Some(4),
+            Self::F   => Some(5),
+            Self::Ff  => Some(6),
+            Self::Fff => Some(7),
+            Self::Sfz => None,
+            Self::Fp  => Some(5),
+        }
+    }
+}
+
+impl DynamicsTable {
+    /// Create a table with default intensities, where `fff` has an intensity
+    /// of `1.0`.
+    pub fn new() -> Self {
+        Self {
+            levels: [0.05, 0.1, 0.2, 0.3, 0.45, 0.6, 0.8, 1.0],
+            accents: [1.3, 1.6, 2.0],
+        }
+    }
+
+    /// Set the intensity of a dynamic marking. Setting `Fp` sets forte, and
+    /// setting `Sfz` sets the factor of `Accent::Sforzando`.
+    pub fn set(mut self, dynamic: Dynamic, intensity: f32) -> Self {
+        match dynamic.level_index() {
+            Some(index) => self.levels[index] = intensity,
+            None => self.accents[Self::accent_index(Accent::Sforzando)] = intensity,
+        }
+        self
+    }
+
+    /// Set the loudness of a dynamic marking in dB, where 0dB is an intensity
+    /// of `1.0`.
+    pub fn set_db(self, dynamic: Dynamic, db: f32) -> Self {
+        self.set(dynamic, db_to_amplitude(db))
+    }
+
+    /// Set the factor the intensity of an accented note is multiplied with.
+    pub fn set_accent(mut self, accent: Accent, factor: f32) -> Self {
+        self.accents[Self::accent_index(accent)] = factor;
+        self
+    }
+
+    /// Get the intensity of a dynamic marking. Returns `None` for `Sfz`, as it
+    /// doesn't have an intensity on its own.
+    pub fn intensity(&self, dynamic: Dynamic) -> Option<f32> {
+        dynamic.level_index().map(|index| self.levels[index])
+    }
+
+    /// Get the factor for an accent.
+    pub fn accent_factor(&self, accent: Accent) -> f32 {
+        self.accents[Self::accent_index(accent)]
+    }
+
+    fn accent_index(accent: Accent) -> usize {
+        match accent {
+            Accent::Accent => 0,
+            Accent::Marcato => 1,
+            Accent::Sforzando => 2,
+        }
+    }
+}
+
+impl Default for DynamicsTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicsCurve {
+    /// Use a `Curve` for the shape of the change. The curve is evaluated from
+    /// x = 0 to x = 1, and should go from y = 0 to y = 1.
+    pub fn custom<C: Curve + Send + Sync + 'static>(curve: C) -> Self {
+        Self::Custom(Arc::new(curve))
+    }
+
+    /// Interpolate from intensity `a` to `b`, where `t == 0` results in `a`
+    /// and `t == 1` results in `b`.
+    pub fn interpolate(&self, a: f32, b: f32, t: f32) -> f32 {
+        match self {
+            Self::Linear => a + (b - a) * t,
+
+            Self::Logarithmic => Self::interpolate_log(a, b, t),
+
+            Self::Exponential => a + b - Self::interpolate_log(a, b, 1.0 - t),
+
+            Self::SCurve => {
+                let t = 3.0*t*t - 2.0*t*t*t;
+                a + (b - a) * t
+            }
+
+            Self::Custom(curve) => a + (b - a) * curve.get(t),
+        }
+    }
+
+    fn interpolate_log(a: f32, b: f32, t: f32) -> f32 {
+        if a == b {
+            return a;
+        }
+
+        let a_clamped = a.max(MIN_LOG_INTENSITY);
+        let b_clamped = b.max(MIN_LOG_INTENSITY);
+        let value = ScaleType::Logarithmic.interpolate(a_clamped, b_clamped, t);
+
+        // Map the clamped range back, so the end points are exact
+        let t_log = (value - a_clamped) / (b_clamped - a_clamped);
+        a + (b - a) * t_log
+    }
+}
+
+impl From<ScaleType> for DynamicsCurve {
+    fn from(scale_type: ScaleType) -> Self {
+        match scale_type {
+            ScaleType::Linear => Self::Linear,
+            ScaleType::Logarithmic => Self::Logarithmic,
+        }
+    }
+}
+
+impl DynamicsChange {
+    /// Multiply the intensity of the whole change.
+    pub fn scale(&mut self, factor: f32) {
+        self.intensity = self.intensity.start * factor..self.intensity.end * factor;
+    }
+
+    /// Get the intensity at `t` from `0.0` (start of the tone) to `1.0` (end
+    /// of the tone).
+    pub fn intensity_at(&self, t: f32) -> f32 {
+        let position = self.position.start + (self.position.end - self.position.start) * t;
+        let position = position.min(1.0);
+        self.curve.interpolate(self.intensity.start, self.intensity.end, position)
+    }
+}
+
+mod tests;
@@ -0,0 +1,85 @@
+#![cfg(test)]
+
+use super::*;
+use crate::instrument::curve::linear_curve::LinearCurve;
+
+fn assert_eq_f32(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-4, "{a} != {b}");
+}
+
+#[test]
+fn end_points_are_exact() {
+    let curves = [
+        DynamicsCurve::Linear,
+        DynamicsCurve::Logarithmic,
+        DynamicsCurve::Exponential,
+        DynamicsCurve::SCurve,
+        DynamicsCurve::custom(LinearCurve::new().add_point(0.0, 0.0).add_point(1.0, 1.0)),
+    ];
+
+    for curve in curves {
+        for (a, b) in [(0.1, 0.9), (0.9, 0.0), (0.0, 1.0)] {
+            assert_eq_f32(curve.interpolate(a, b, 0.0), a);
+            assert_eq_f32(curve.interpolate(a, b, 1.0), b);
+        }
+    }
+}
+
+#[test]
+fn logarithmic_is_even_in_db() {
+    let curve = DynamicsCurve::Logarithmic;
+
+    // Every quarter of the change doubles the intensity
+    assert_eq_f32(curve.interpolate(0.0625, 1.0, 0.25), 0.125);
+    assert_eq_f32(curve.interpolate(0.0625, 1.0, 0.5), 0.25);
+    assert_eq_f32(curve.interpolate(0.0625, 1.0, 0.75), 0.5);
+}
+
+#[test]
+fn curve_shapes() {
+    let (a, b) = (0.1, 0.9);
+    let linear = DynamicsCurve::Linear.interpolate(a, b, 0.25);
+
+    assert!(DynamicsCurve::Logarithmic.interpolate(a, b, 0.25) < linear);
+    assert!(DynamicsCurve::Exponential.interpolate(a, b, 0.25) > linear);
+    assert!(DynamicsCurve::SCurve.interpolate(a, b, 0.25) < linear);
+
+    assert_eq_f32(DynamicsCurve::SCurve.interpolate(a, b, 0.5), 0.5);
+}
+
+#[test]
+fn change_within_tone() {
+    let change = DynamicsChange {
+        curve: DynamicsCurve::Linear,
+        intensity: 0.0..1.0,
+        position: 0.5..0.75,
+    };
+
+    assert_eq_f32(change.intensity_at(0.0), 0.5);
+    assert_eq_f32(change.intensity_at(0.5), 0.625);
+    assert_eq_f32(change.intensity_at(1.0), 0.75);
+}
+
+#[test]
+fn dynamics_table() {
+    let table = DynamicsTable::new()
+        .set(Dynamic::Mf, 0.5)
+        .set_db(Dynamic::Ppp, -40.0)
+        .set(Dynamic::Sfz, 2.5);
+
+    assert_eq!(table.intensity(Dynamic::Mf), Some(0.5));
+    assert_eq_f32(table.intensity(Dynamic::Ppp).unwrap(), 0.01);
+    assert_eq!(table.intensity(Dynamic::Fp), table.intensity(Dynamic::F));
+    assert_eq!(table.intensity(Dynamic::Sfz), None);
+    assert_eq!(table.accent_factor(Accent::Sforzando), 2.5);
+
+    // Levels get louder
+    let levels = [
+        Dynamic::Ppp, Dynamic::Pp, Dynamic::P, Dynamic::Mp,
+        Dynamic::Mf, Dynamic::F, Dynamic::Ff, Dynamic::Fff,
+    ];
+    let default = DynamicsTable::new();
+    for pair in levels.windows(2) {
+        assert!(default.intensity(pair[0]) < default.intensity(pair[1]));
+    }
+}
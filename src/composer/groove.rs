@@ -0,0 +1,184 @@
+use super::Length;
+use crate::file_export::export_info::{Tone, start_times};
+
+use std::time::Duration;
+
+// Tones this close to a grid position (relative to a grid step) count as
+// being on the grid. Durations are not exact, so there's some tolerance.
+const GRID_TOLERANCE: f64 = 0.01;
+
+/// Timing and velocity offsets for the positions of a grid, which give a track
+/// a shuffle or groove feel. The notes are placed straight on the track, and
+/// the groove is applied when converting the track to an `ExportTrack`.
+/// 
+/// The steps of the groove repeat over the grid. Every tone that starts on a
+/// grid position is shifted by the timing of the step at that position, and its
+/// intensity is multiplied with the velocity of the step. Tones that don't
+/// start on the grid are left alone. On a `MeasureTrack`, the grid starts over
+/// at every measure, so every downbeat gets the first step, even after a
+/// pickup or in measures with an odd number of grid positions.
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+/// 
+/// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+/// 
+/// // Triplet feel for eighths
+/// track.set_groove(Groove::swing(EIGTH, 66.7));
+/// 
+/// sequential_notes!(track, EIGTH,
+///     first(4), second(4), third(4), fourth(4),
+///     fifth(4), fourth(4), third(4), second(4),
+/// );
+/// track.measure().unwrap();
+/// 
+/// // A custom groove that pushes the third sixteenth and weakens the offbeats
+/// let groove = Groove::new(SIXTEENTH)
+///     .add_step(0.0, 1.0)
+///     .add_step(0.05, 0.8)
+///     .add_step(-0.1, 1.0)
+///     .add_step(0.05, 0.8);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Groove {
+    grid: Length,
+    steps: Vec<GrooveStep>,
+}
+
+/// A single position of a `Groove`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrooveStep {
+    /// The timing offset as a fraction of a grid step. Positive values delay
+    /// the tone, negative values play it early.
+    pub timing: f32,
+    /// The factor the intensity is multiplied with.
+    pub velocity: f32,
+}
+
+impl Groove {
+    /// Create a groove without steps on a grid of the given length. This
+    /// doesn't change anything until steps are added.
+    pub fn new(grid: Length) -> Self {
+        Self {
+            grid,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Create a swing groove for notes of the `grid` length (usually eighths
+    /// or sixteenths). The percentage is the part of a pair of notes that the
+    /// first note takes: `50.0` is straight, `66.7` is a triplet feel, and
+    /// `75.0` is a dotted feel.
+    pub fn swing(grid: Length, percentage: f32) -> Self {
+        let timing = percentage / 100.0 * 2.0 - 1.0;
+
+        Self::new(grid)
+            .add_step(0.0, 1.0)
+            .add_step(timing, 1.0)
+    }
+
+    /// Add a step with a timing offset (as a fraction of a grid step) and a
+    /// velocity factor.
+    pub fn add_step(mut self, timing: f32, velocity: f32) -> Self {
+        self.steps.push(GrooveStep {
+            timing,
+            velocity,
+        });
+        self
+    }
+
+    /// Get the step at an index of the grid. The steps repeat over the grid.
+    pub fn step(&self, index: usize) -> Option<GrooveStep> {
+        if self.steps.is_empty() {
+            return None;
+        }
+
+        Some(self.steps[index % self.steps.len()])
+    }
+
+    /// Shift the tones and change their intensity. All tones get an explicit
+    /// start time.
+    pub(crate) fn apply<T>(&self, tones: &mut [Tone<T>], bpm: f32) {
+        self.apply_in_measures(tones, bpm, &[0.0]);
+    }
+
+    /// Like `apply()`, but the grid starts over at every measure. The starts
+    /// of the measures are given in seconds; a pickup measure starts before
+    /// the track, at the time a full measure would start.
+    pub(crate) fn apply_in_measures<T>(&self, tones: &mut [Tone<T>], bpm: f32, measure_starts: &[f64]) {
+        let grid = Grid {
+            duration: self.grid.to_duration(bpm).as_secs_f64(),
+            measure_starts,
+        };
+        let starts = start_times(tones);
+
+        for (tone, start) in tones.iter_mut().zip(starts) {
+            let start = start.as_secs_f64();
+            let end = start + tone.play_duration.as_secs_f64();
+
+            // The end moves with the next grid position, so the tone keeps
+            // ending where the next tone starts
+            let shifted_start = f64::max(start + self.offset(start, &grid), 0.0);
+            let shifted_end = f64::max(end + self.offset(end, &grid), shifted_start);
+
+            let play_duration = Duration::from_secs_f64(shifted_end - shifted_start);
+            let factor = play_duration.as_secs_f64() / tone.play_duration.as_secs_f64();
+            if factor.is_finite() {
+                tone.tone_duration = tone.tone_duration.mul_f64(factor);
+            }
+
+            tone.start = Some(Duration::from_secs_f64(shifted_start));
+            tone.play_duration = play_duration;
+
+            if let Some(step) = self.step_at(start, &grid) {
+                tone.intensity = tone.intensity.start * step.velocity..tone.intensity.end * step.velocity;
+                if let Some(dynamics) = &mut tone.dynamics {
+                    dynamics.scale(step.velocity);
+                }
+            }
+        }
+    }
+
+    fn step_at(&self, time: f64, grid: &Grid) -> Option<GrooveStep> {
+        let position = grid.position(time);
+        let index = position.round();
+
+        if (position - index).abs() > GRID_TOLERANCE {
+            return None;
+        }
+
+        self.step(index as usize)
+    }
+
+    fn offset(&self, time: f64, grid: &Grid) -> f64 {
+        match self.step_at(time, grid) {
+            Some(step) => step.timing as f64 * grid.duration,
+            None => 0.0,
+        }
+    }
+}
+
+/// The grid of a groove in seconds, which starts over at every measure.
+struct Grid<'a> {
+    duration: f64,
+    measure_starts: &'a [f64],
+}
+
+impl Grid<'_> {
+    /// The position in grid steps since the start of the measure. A time
+    /// slightly before a measure starts still belongs to that measure.
+    fn position(&self, time: f64) -> f64 {
+        let tolerance = GRID_TOLERANCE * self.duration;
+        let measure_start = self.measure_starts.iter()
+            .rev()
+            .find(|start| **start <= time + tolerance)
+            .copied()
+            .unwrap_or(0.0);
+
+        (time - measure_start) / self.duration
+    }
+}
+
+mod tests;
@@ -0,0 +1,130 @@
+#![cfg(test)]
+
+use super::*;
+use crate::prelude::length::*;
+
+// At 120 bpm, an eighth is 0.25s
+const BPM: f32 = 120.0;
+
+fn straight_tones(count: usize) -> Vec<Tone<i32>> {
+    (0..count)
+        .map(|_| Tone {
+            play_duration: Duration::from_millis(250),
+            tone_duration: Duration::from_millis(250),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn assert_duration_eq(a: Duration, b: Duration) {
+    let delta = (a.as_secs_f64() - b.as_secs_f64()).abs();
+    assert!(delta < 1e-4, "{a:?} != {b:?}");
+}
+
+#[test]
+fn straight_swing_is_unchanged() {
+    let mut tones = straight_tones(4);
+    Groove::swing(EIGTH, 50.0).apply(&mut tones, BPM);
+
+    for (i, tone) in tones.iter().enumerate() {
+        assert_duration_eq(tone.start.unwrap(), Duration::from_millis(250 * i as u64));
+        assert_duration_eq(tone.play_duration, Duration::from_millis(250));
+    }
+}
+
+#[test]
+fn swing_shifts_offbeats() {
+    let mut tones = straight_tones(4);
+    Groove::swing(EIGTH, 75.0).apply(&mut tones, BPM);
+
+    let starts: Vec<u64> = tones.iter().map(|tone| tone.start.unwrap().as_millis() as u64).collect();
+    let durations: Vec<u64> = tones.iter().map(|tone| tone.play_duration.as_millis() as u64).collect();
+
+    assert_eq!(starts, vec![0, 375, 500, 875]);
+    assert_eq!(durations, vec![375, 125, 375, 125]);
+}
+
+#[test]
+fn groove_velocity() {
+    let mut tones = straight_tones(4);
+    let groove = Groove::new(EIGTH)
+        .add_step(0.0, 1.0)
+        .add_step(0.0, 0.5);
+
+    groove.apply(&mut tones, BPM);
+
+    let intensities: Vec<f32> = tones.iter().map(|tone| tone.intensity.start).collect();
+    assert_eq!(intensities, vec![1.0, 0.5, 1.0, 0.5]);
+}
+
+#[test]
+fn off_grid_tones_unchanged() {
+    // A dotted eighth, then a sixteenth that is not on the eighth grid
+    let mut tones: Vec<Tone<i32>> = vec![
+        Tone {
+            play_duration: Duration::from_millis(375),
+            tone_duration: Duration::from_millis(375),
+            ..Default::default()
+        },
+        Tone {
+            play_duration: Duration::from_millis(125),
+            tone_duration: Duration::from_millis(125),
+            ..Default::default()
+        },
+    ];
+
+    Groove::new(EIGTH).add_step(0.0, 1.0).add_step(0.5, 0.5).apply(&mut tones, BPM);
+
+    assert_duration_eq(tones[1].start.unwrap(), Duration::from_millis(375));
+    assert_eq!(tones[1].intensity, 1.0..1.0);
+}
+
+#[test]
+fn grid_starts_at_every_measure() {
+    use crate::prelude::*;
+    use crate::prelude::tet12::*;
+
+    const SETTINGS: CompositionSettings = CompositionSettings {
+        sample_rate: 44100,
+    };
+
+    let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(7, 8));
+    track.set_groove(Groove::swing(EIGTH, 75.0));
+
+    track.set_pickup(EIGTH);
+    track.note(EIGTH, fifth(3));
+    track.measure().unwrap();
+
+    for _ in 0..3 {
+        for _ in 0..7 {
+            track.note(EIGTH, first(4));
+        }
+        track.measure().unwrap();
+    }
+
+    let info = SectionInfo {
+        bpm: BPM,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let tones = track.convert_to_export_track(info).tones;
+
+    // The pickup is the last eighth of a measure, which is on the grid
+    let mut expected = vec![0];
+    for measure in 0..3 {
+        let downbeat = 250 + measure * 1750;
+
+        for step in 0..7 {
+            let swing = if step % 2 == 1 { 125 } else { 0 };
+            expected.push(downbeat + step * 250 + swing);
+        }
+    }
+
+    let starts: Vec<u64> = tones.iter()
+        .map(|tone| tone.start.unwrap().as_secs_f64() * 1000.0)
+        .map(|millis| millis.round() as u64)
+        .collect();
+
+    assert_eq!(starts, expected);
+}
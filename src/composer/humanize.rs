@@ -0,0 +1,139 @@
+use super::parameter::PITCH_BEND;
+use crate::file_export::export_info::{Tone, start_times};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::time::Duration;
+
+/// Random variations of timing, duration, intensity and pitch, which make a
+/// track sound less mechanical. It's applied when converting the track to an
+/// `ExportTrack`, after a `Groove`.
+/// 
+/// Every amount is the maximum variation in both directions. The variations
+/// are bounded by the length of the tone, so a tone never moves by more than a
+/// quarter of its length. The random values are generated from a seed, so the
+/// same composition is rendered the same way every time. The seed is `0`
+/// unless set otherwise, so tracks with the same humanization vary in lockstep;
+/// give every track its own seed with `set_seed()`.
+/// 
+/// The detune is added to the `PITCH_BEND` parameter of the tone. The
+/// predefined generators like `SineGenerator` bend their pitch by it; other
+/// instruments need to read it with `Tone::parameter()`.
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use std::time::Duration;
+/// 
+/// let humanize = Humanize::new()
+///     .set_timing(Duration::from_millis(10))
+///     .set_duration(0.1)
+///     .set_intensity(0.05)
+///     .set_detune(5.0)
+///     .set_seed(42);
+/// 
+/// let mut track: UnboundTrack<TET12ScaledTone, _> = UnboundTrack::new(predefined::SineGenerator);
+/// track.set_humanize(humanize);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Humanize {
+    timing: Duration,
+    duration: f32,
+    intensity: f32,
+    detune: f32,
+    seed: u64,
+}
+
+impl Humanize {
+    /// Create a humanization without any variation.
+    pub fn new() -> Self {
+        Self {
+            timing: Duration::ZERO,
+            duration: 0.0,
+            intensity: 0.0,
+            detune: 0.0,
+            seed: 0,
+        }
+    }
+
+    /// Set the maximum offset of the start of a tone.
+    pub fn set_timing(mut self, timing: Duration) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Set the maximum change of the duration a tone is played, relative to
+    /// its duration (e.g. `0.1` for 10%).
+    pub fn set_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set the maximum change of the intensity, relative to the intensity.
+    pub fn set_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Set the maximum detune in cents.
+    pub fn set_detune(mut self, detune: f32) -> Self {
+        self.detune = detune;
+        self
+    }
+
+    /// Set the seed for the random variations. Use a different seed for every
+    /// track, otherwise the tracks vary the same way.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Vary the tones. All tones get an explicit start time.
+    pub(crate) fn apply<T>(&self, tones: &mut [Tone<T>]) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let starts = start_times(tones);
+
+        for (tone, start) in tones.iter_mut().zip(starts) {
+            tone.start = Some(start);
+
+            // Pauses stay where they are
+            if tone.concrete_values.is_empty() {
+                continue;
+            }
+
+            let max_offset = f64::min(
+                self.timing.as_secs_f64(),
+                tone.play_duration.as_secs_f64() / 4.0,
+            );
+            let offset = max_offset * rng.gen_range(-1.0..=1.0);
+            if offset != 0.0 {
+                tone.start = Some(Duration::from_secs_f64(f64::max(start.as_secs_f64() + offset, 0.0)));
+            }
+
+            let duration_factor = 1.0 + self.duration.clamp(0.0, 1.0) as f64 * rng.gen_range(-1.0..=1.0);
+            if duration_factor != 1.0 {
+                tone.tone_duration = tone.tone_duration.mul_f64(duration_factor);
+            }
+
+            let intensity_factor = 1.0 + self.intensity.clamp(0.0, 1.0) * rng.gen_range(-1.0..=1.0);
+            tone.intensity = tone.intensity.start * intensity_factor..tone.intensity.end * intensity_factor;
+            if let Some(dynamics) = &mut tone.dynamics {
+                dynamics.scale(intensity_factor);
+            }
+
+            let detune = self.detune * rng.gen_range(-1.0..=1.0) / 100.0;
+            if detune != 0.0 {
+                let bend = tone.parameters.get(PITCH_BEND).cloned()
+                    .unwrap_or(PITCH_BEND.default_value()..PITCH_BEND.default_value());
+                tone.parameters.set(PITCH_BEND, bend.start + detune..bend.end + detune);
+            }
+        }
+    }
+}
+
+impl Default for Humanize {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+mod tests;
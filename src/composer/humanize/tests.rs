@@ -0,0 +1,123 @@
+#![cfg(test)]
+
+use super::*;
+
+fn tones(count: usize) -> Vec<Tone<i32>> {
+    (0..count)
+        .map(|i| Tone {
+            concrete_values: vec![i as i32],
+            play_duration: Duration::from_millis(200),
+            tone_duration: Duration::from_millis(200),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn humanize() -> Humanize {
+    Humanize::new()
+        .set_timing(Duration::from_millis(20))
+        .set_duration(0.1)
+        .set_intensity(0.2)
+        .set_detune(10.0)
+}
+
+#[test]
+fn no_variation() {
+    let mut result = tones(4);
+    Humanize::new().apply(&mut result);
+
+    for (i, tone) in result.iter().enumerate() {
+        assert_eq!(tone.start, Some(Duration::from_millis(200 * i as u64)));
+        assert_eq!(tone.tone_duration, Duration::from_millis(200));
+        assert_eq!(tone.intensity, 1.0..1.0);
+        assert!(tone.parameters.is_empty());
+    }
+}
+
+#[test]
+fn variation_is_bounded() {
+    let mut result = tones(50);
+    humanize().apply(&mut result);
+
+    for (i, tone) in result.iter().enumerate() {
+        let offset = tone.start.unwrap().as_secs_f64() - 0.2 * i as f64;
+        assert!(offset.abs() <= 0.020 + 1e-6, "offset {offset}");
+
+        let duration = tone.tone_duration.as_secs_f64();
+        assert!((0.18 - 1e-6..=0.22 + 1e-6).contains(&duration), "duration {duration}");
+
+        assert!((0.8..=1.2).contains(&tone.intensity.start));
+
+        let bend = tone.parameters.get(PITCH_BEND).unwrap();
+        assert!(bend.start.abs() <= 0.1);
+    }
+}
+
+#[test]
+fn timing_bounded_by_tone_length() {
+    let mut result = tones(20);
+    Humanize::new()
+        .set_timing(Duration::from_secs(1))
+        .apply(&mut result);
+
+    for (i, tone) in result.iter().enumerate() {
+        let offset = tone.start.unwrap().as_secs_f64() - 0.2 * i as f64;
+        assert!(offset.abs() <= 0.05 + 1e-6, "offset {offset}");
+    }
+}
+
+#[test]
+fn seeded() {
+    let mut a = tones(8);
+    let mut b = tones(8);
+    let mut c = tones(8);
+
+    humanize().apply(&mut a);
+    humanize().apply(&mut b);
+    humanize().set_seed(1).apply(&mut c);
+
+    let starts = |tones: &[Tone<i32>]| tones.iter().map(|tone| tone.start).collect::<Vec<_>>();
+
+    assert_eq!(starts(&a), starts(&b));
+    assert_ne!(starts(&a), starts(&c));
+}
+
+#[test]
+fn detune_bends_rendered_pitch() {
+    use crate::file_export::{CompositionSettings, SoundBuffer};
+    use crate::instrument::Instrument;
+    use crate::instrument::predefined::{SineGenerator, sine_wave};
+    use crate::instrument::predefined::tet12::TET12ConcreteTone;
+
+    const SETTINGS: CompositionSettings = CompositionSettings {
+        sample_rate: 8000,
+    };
+
+    let render = |tone: &Tone<TET12ConcreteTone>| {
+        let mut buffer = SoundBuffer::from_parts(Vec::new(), 800, SETTINGS);
+        SineGenerator.render(tone, &mut buffer);
+        buffer.samples
+    };
+
+    let mut tones = vec![Tone {
+        concrete_values: vec![TET12ConcreteTone(0)],
+        play_duration: Duration::from_millis(100),
+        tone_duration: Duration::from_millis(100),
+        ..Default::default()
+    }];
+    let straight = render(&tones[0]);
+
+    Humanize::new().set_detune(50.0).apply(&mut tones);
+    let bend = tones[0].parameters.get(PITCH_BEND).unwrap().start;
+    assert_ne!(bend, 0.0);
+
+    let detuned = render(&tones[0]);
+    assert_ne!(detuned, straight);
+
+    // A4 is bent by the detune
+    let frequency = 440.0 * 2_f64.powf(bend as f64 / 12.0);
+    for (i, sample) in detuned.iter().enumerate() {
+        let expected = sine_wave(frequency, Duration::from_secs_f64(i as f64 / 8000.0));
+        assert!((sample - expected).abs() < 1e-3, "sample {i}: {sample} != {expected}");
+    }
+}
@@ -1,13 +1,22 @@
 use super::UnboundTrack;
+use super::note_state::NoteState;
 
-use super::{Note, ScaledValue, length, Length};
+use super::{Note, ScaledValue, Length};
 use super::note::DynamicsFlag;
+use super::note::length::ZERO;
+use super::parameter::Parameter;
+use super::dynamics::{DynamicsCurve, Dynamic, DynamicsTable};
 
 use super::{TimeSignature, SectionInfo, MusicTrack};
 use super::ExportTrack;
+use super::groove::Groove;
+use super::humanize::Humanize;
+use crate::file_export::export_info::start_times;
 
 use crate::instrument::Instrument;
 
+use std::time::Duration;
+
 /// An implementation of MusicTrack with additional rules to ensure that
 /// Measures are filled with notes correctly. Use this as the standard Track.
 /// 
@@ -16,6 +25,32 @@ use crate::instrument::Instrument;
 /// (e.g. the first beat).
 /// 
 /// Read the front page of the crate for examples on how to use this.
+/// 
+/// A track can have multiple voices, e.g. a held bass note under moving
+/// chords. Select the voice that notes are placed in with `set_voice()`. Every
+/// voice is validated on its own when placing the end of a measure. A voice
+/// without notes in a measure is filled with pauses.
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+/// 
+/// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+/// 
+/// track.set_voice(0);
+/// sequential_notes!(track, QUARTER, fifth(4), sixth(4), seventh(4), first(5));
+/// 
+/// track.set_voice(1);
+/// track.note(WHOLE, first(3));
+/// 
+/// track.measure().unwrap();
+/// ```
+/// 
+/// The dynamics and other settings of the track are shared between all
+/// voices. A dynamics or parameter change ends in every voice it was started
+/// in, even if it's ended in another voice. Instrument changes are placed at
+/// the position of the active voice, and apply to all voices from that time.
 pub struct MeasureTrack<T, U>
 where 
     T: ScaledValue<ConcreteValue = U::ConcreteValue>,
@@ -23,18 +58,25 @@ where
 {
     active_measure: Option<Measure<T>>,
     unbound_track: UnboundTrack<T, U>,
+    voice_tracks: Vec<UnboundTrack<T, U>>,
+    active_voice: usize,
     time_signature: TimeSignature,
-
-    current_intensity: f32,
-    current_play_fraction: f32,
-
-    next_note_dynamic_flag: Option<DynamicsFlag>,
+    pickup: Option<Length>,
+    groove: Option<Groove>,
+    humanize: Option<Humanize>,
+    completed_measures: Vec<(TimeSignature, Vec<Length>)>,
+
+    note_state: NoteState,
+    dynamic_change_voices: Vec<usize>,
+    parameter_change_voices: Vec<(Parameter, usize)>,
 }
 
 /// A single measure; managed by `MeasureTrack`
 pub struct Measure<T: ScaledValue> {
     time_signature: TimeSignature,
-    notes: Vec<Note<T>>,
+    voices: Vec<Vec<Note<T>>>,
+    active_voice: usize,
+    pickup: Option<Length>,
 }
 
 impl<T, U> MusicTrack<T, U> for MeasureTrack<T, U>
@@ -43,28 +85,35 @@ where
     U: Instrument,
 {
     fn notes(&mut self, length: Length, values: Vec<T>) -> &mut Note<T> {
-        let play_fraction = self.current_play_fraction;
-        
-        let dynamics_flag = self.next_note_dynamic_flag.take().unwrap_or(DynamicsFlag::None);
-        
-        let current_measure_length = self.get_active_measure().get_total_length();
-        let beat_emphasis = self.get_beat_from_position(current_measure_length);
-
-        let intensity = self.current_intensity * beat_emphasis;
-        
         let active_measure = self.get_active_measure();
+        let beat_emphasis = active_measure.time_signature
+            .emphasis_at(&active_measure.position());
+
+        let active_voice = self.active_voice;
+        let previous = last_note_of(
+            self.active_measure.as_mut().unwrap(),
+            &mut self.unbound_track,
+            &mut self.voice_tracks,
+            active_voice,
+        );
+        let note = self.note_state.next_note(length, values, previous, beat_emphasis);
+
+        // Remember the voices with open changes, so they can all be ended
+        if note.dynamics_flag == DynamicsFlag::StartChange && !self.dynamic_change_voices.contains(&active_voice) {
+            self.dynamic_change_voices.push(active_voice);
+        }
 
-        active_measure.notes.push(Note {
-            values,
-            length,
-            intensity,
-            play_fraction,
-            dynamics_flag,
-            ..Default::default()
-        });
+        for key in note.parameter_flags.keys() {
+            if !self.parameter_change_voices.contains(&(key, active_voice)) {
+                self.parameter_change_voices.push((key, active_voice));
+            }
+        }
 
-        let last_index = active_measure.notes.len() - 1;
-        return &mut active_measure.notes[last_index];
+        let notes = self.get_active_measure().notes_mut();
+        notes.push(note);
+
+        let last_index = notes.len() - 1;
+        return &mut notes[last_index];
     }
 
     fn note(&mut self, length: Length, value: T) -> &mut Note<T> {
@@ -76,38 +125,115 @@ where
     }
 
     fn start_dynamic_change(&mut self) {
-        self.next_note_dynamic_flag = Some(DynamicsFlag::StartChange);
+        self.start_dynamic_change_with(DynamicsCurve::Linear);
+    }
+
+    fn start_dynamic_change_with(&mut self, curve: DynamicsCurve) {
+        self.note_state.start_dynamic_change(curve);
     }
 
     fn end_dynamic_change(&mut self, intensity: f32) {
-        let active_note = self.get_active_note().unwrap();
+        let started_voices = std::mem::take(&mut self.dynamic_change_voices);
+        self.note_state.end_dynamic_change(intensity);
 
-        active_note.dynamics_flag = DynamicsFlag::EndChange;
-        active_note.intensity = intensity;
-        self.current_intensity = intensity;
+        self.end_change(&started_voices, |note, started| {
+            NoteState::end_dynamics_on(note, started, intensity);
+        });
     }
 
     fn set_intensity(&mut self, intensity: f32) {
-        self.current_intensity = intensity;
+        self.note_state.set_intensity(intensity);
+    }
+
+    fn set_dynamic(&mut self, dynamic: Dynamic) {
+        self.note_state.set_dynamic(dynamic, self.unbound_track.dynamics_table());
     }
 
     fn set_play_fraction(&mut self, play_fraction: f32) {
-        self.current_play_fraction = play_fraction;
+        self.note_state.set_play_fraction(play_fraction);
     }
 
-    fn get_active_note(&mut self) -> Option<&mut Note<T>> {
-        let active_measure_empty = self.get_active_measure().is_empty();
+    fn set_parameter(&mut self, key: Parameter, value: f32) {
+        self.note_state.set_parameter(key, value);
+    }
+
+    fn start_parameter_change(&mut self, key: Parameter) {
+        self.note_state.start_parameter_change(key);
+    }
+
+    fn end_parameter_change(&mut self, key: Parameter, value: f32) {
+        let started_voices: Vec<usize> = self.parameter_change_voices.iter()
+            .filter(|(started_key, _)| *started_key == key)
+            .map(|(_, voice)| *voice)
+            .collect();
+        self.parameter_change_voices.retain(|(started_key, _)| *started_key != key);
+        self.note_state.end_parameter_change(key, value);
+
+        self.end_change(&started_voices, |note, started| {
+            NoteState::end_parameter_on(note, started, key, value);
+        });
+    }
+
+    fn start_slur(&mut self) {
+        self.note_state.start_slur();
+    }
+
+    fn end_slur(&mut self) {
+        self.note_state.end_slur();
+    }
 
-        if active_measure_empty {
-            return self.unbound_track.get_active_note();
+    fn set_instrument(&mut self, instrument: U) {
+        let active_voice = self.active_voice;
+        let notes_in_measure = self.get_active_measure().voice(active_voice).len();
+        let index = self.voice_track(active_voice).num_notes() + notes_in_measure;
+
+        // A change at the start of the track replaces the instrument of the
+        // first voice, which is the instrument of the merged track
+        match index {
+            0 => self.unbound_track.set_instrument_at(0, instrument),
+            _ => self.voice_track(active_voice).set_instrument_at(index, instrument),
         }
+    }
 
-        let active_measure = self.get_active_measure();
-        return active_measure.notes.last_mut();
+    fn get_active_note(&mut self) -> Option<&mut Note<T>> {
+        self.last_note(self.active_voice)
+    }
+
+    fn remaining_measure_length(&self) -> Option<Length> {
+        let active_measure = self.active_measure.as_ref().unwrap();
+        let position: Length = active_measure.position().into_iter().sum();
+        let measure_length = active_measure.time_signature.measure_length;
+
+        // An overfilled measure has no space left
+        Some(measure_length.max(position) - position)
+    }
+
+    fn end_measure(&mut self) -> Result<(), &str> {
+        self.measure()
     }
 
     fn convert_to_export_track(&self, section_info: SectionInfo) -> ExportTrack<U> {
-        self.unbound_track.convert_to_export_track(section_info)
+        let mut export_track = self.unbound_track.convert_to_export_track(section_info);
+
+        if !self.voice_tracks.is_empty() {
+            let voices = self.voice_tracks.iter()
+                .map(|track| track.convert_to_export_track(section_info))
+                .collect();
+
+            export_track = merge_voices(export_track, voices);
+        }
+
+        if let Some(groove) = &self.groove {
+            let measure_starts = self.measure_starts(section_info.bpm);
+            groove.apply_in_measures(&mut export_track.tones, section_info.bpm, &measure_starts);
+        }
+
+        if let Some(humanize) = &self.humanize {
+            humanize.apply(&mut export_track.tones);
+        }
+
+        export_track.time_signatures = self.time_signature_changes(section_info.bpm);
+        export_track
     }
 }
 
@@ -121,13 +247,132 @@ where
         Self {
             active_measure: Some(Measure::new(time_signature.clone())),
             unbound_track: UnboundTrack::new(instrument),
+            voice_tracks: Vec::new(),
+            active_voice: 0,
             time_signature,
-            current_intensity: 0.5,
-            current_play_fraction: 1.0,
-            next_note_dynamic_flag: None,
+            pickup: None,
+            groove: None,
+            humanize: None,
+            completed_measures: Vec::new(),
+            note_state: NoteState::new(),
+            dynamic_change_voices: Vec::new(),
+            parameter_change_voices: Vec::new(),
         }
     }
 
+    /// Replace the table used for looking up `Dynamic` markings and accents.
+    pub fn set_dynamics_table(&mut self, dynamics_table: DynamicsTable) {
+        self.unbound_track.set_dynamics_table(dynamics_table);
+
+        for voice_track in self.voice_tracks.iter_mut() {
+            voice_track.set_dynamics_table(dynamics_table);
+        }
+    }
+
+    /// Apply a swing or groove to all voices of the track when converting it.
+    /// The notes are placed straight, see `Groove` for details.
+    pub fn set_groove(&mut self, groove: Groove) {
+        self.groove = Some(groove);
+    }
+
+    /// Apply random variations to all voices of the track when converting it,
+    /// see `Humanize` for details.
+    pub fn set_humanize(&mut self, humanize: Humanize) {
+        self.humanize = Some(humanize);
+    }
+
+    /// Change the time signature for the active measure and all following
+    /// measures. Call this before placing the first note of the measure, as
+    /// the beat emphasis is applied when a note is placed.
+    /// 
+    /// ```
+    /// use synth_music::prelude::*;
+    /// use tet12::*;
+    /// use length::*;
+    /// 
+    /// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+    /// track.note(WHOLE, first(4));
+    /// track.measure().unwrap();
+    /// 
+    /// track.set_time_signature(TimeSignature::new(7, 8));
+    /// sequential_notes!(track, EIGTH, first(4), second(4), third(4), fourth(4), fifth(4), sixth(4), seventh(4));
+    /// track.measure().unwrap();
+    /// 
+    /// track.set_time_signature(TimeSignature::new(3, 4));
+    /// track.note(HALF.dot(), first(4));
+    /// track.measure().unwrap();
+    /// ```
+    /// 
+    /// Panics if the active measure is a pickup measure that isn't shorter
+    /// than a full measure of the new time signature.
+    pub fn set_time_signature(&mut self, time_signature: TimeSignature) {
+        if let Some(pickup) = self.get_active_measure().pickup {
+            assert_valid_pickup(pickup, &time_signature);
+        }
+
+        self.get_active_measure().override_time_signature(time_signature.clone());
+        self.time_signature = time_signature;
+    }
+
+    /// Declare the first measure as a pickup measure (anacrusis) of the given
+    /// length. The notes of the pickup measure are placed at the end of a full
+    /// measure, so the beat emphasis lines up with the meter. The last measure
+    /// of the track can be completed with `final_measure()`, so that both
+    /// measures together make up a full measure.
+    /// 
+    /// ```
+    /// use synth_music::prelude::*;
+    /// use tet12::*;
+    /// use length::*;
+    /// 
+    /// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(3, 4));
+    /// 
+    /// track.set_pickup(QUARTER);
+    /// track.note(QUARTER, fifth(3));
+    /// track.measure().unwrap();
+    /// 
+    /// sequential_notes!(track, QUARTER, first(4), first(4), second(4));
+    /// track.measure().unwrap();
+    /// 
+    /// track.note(HALF, first(4));
+    /// track.final_measure().unwrap();
+    /// ```
+    /// 
+    /// Panics if notes were already placed on the track, or if the pickup is
+    /// empty or not shorter than a full measure.
+    pub fn set_pickup(&mut self, length: Length) {
+        let measure_empty = self.active_measure.as_ref().unwrap().voices.iter()
+            .all(|notes| notes.is_empty());
+
+        if self.unbound_track.num_notes() > 0 || !measure_empty {
+            panic!("The pickup needs to be declared before placing notes.");
+        }
+
+        assert_valid_pickup(length, &self.get_active_measure().time_signature);
+
+        self.pickup = Some(length);
+        self.get_active_measure().pickup = Some(length);
+    }
+
+    /// Select the voice that the following notes are placed in. Voice `0` is
+    /// the default voice. Selecting a new voice creates it, and all voices
+    /// in between.
+    pub fn set_voice(&mut self, voice: usize) {
+        while self.voice_tracks.len() < voice {
+            let mut voice_track = self.unbound_track.empty_copy();
+
+            // Fill the measures before the voice was created
+            for length in self.unbound_track.note_lengths() {
+                voice_track.pause(length);
+            }
+
+            self.voice_tracks.push(voice_track);
+        }
+
+        self.active_voice = voice;
+        self.get_active_measure().active_voice = voice;
+    }
+
     /// Place the end of a measure, which will automatically validate the
     /// completed measure. An error is returned if the measure is invalid.
     pub fn measure(&mut self) -> Result<(), &str> {
@@ -137,36 +382,137 @@ where
             return Err("Invalid measure bounds");
         }
 
-        let new_measure = Measure::new(self.time_signature.clone());
-        let valid_measure = self.active_measure.replace(new_measure).unwrap();
-
-        self.unbound_track.append_notes(&valid_measure.notes);
+        self.complete_measure();
 
         return Ok(());
     }
 
+    /// Place the end of the last measure, which may be shorter than a full
+    /// measure. If a pickup was declared with `set_pickup()`, the final
+    /// measure and the pickup have to make up a full measure. Otherwise, the
+    /// final measure can have any length up to a full measure.
+    pub fn final_measure(&mut self) -> Result<(), &str> {
+        let pickup = self.pickup;
+        let active_measure_valid = self.get_active_measure().assert_final_measure_bounds(pickup);
+
+        if !active_measure_valid {
+            return Err("Invalid final measure bounds");
+        }
+
+        self.complete_measure();
+
+        Ok(())
+    }
+
     pub fn get_active_measure(&mut self) -> &mut Measure<T> {
         self.active_measure.as_mut().unwrap()
     }
 
-    fn get_beat_from_position(&self, position: Length) -> f32 {
-        let beats = self.time_signature.beats();
+    /// Append the active measure to the voices and start a new measure.
+    fn complete_measure(&mut self) {
+        let mut new_measure = Measure::new(self.time_signature.clone());
+        new_measure.active_voice = self.active_voice;
+        let valid_measure = self.active_measure.replace(new_measure).unwrap();
 
-        let mut position_in_measure = length::ZERO;
+        // Voices without notes are filled with pauses
+        let filled_voice = valid_measure.voices.iter()
+            .find(|notes| !notes.is_empty())
+            .unwrap();
+        let pauses: Vec<Note<T>> = filled_voice.iter()
+            .map(|note| Note {
+                length: note.length,
+                ..Default::default()
+            })
+            .collect();
+
+        let lengths = pauses.iter().map(|note| note.length).collect();
+        self.completed_measures.push((valid_measure.time_signature.clone(), lengths));
+
+        for voice in 0..=self.voice_tracks.len() {
+            let notes = match valid_measure.voices.get(voice) {
+                Some(notes) if !notes.is_empty() => notes,
+                _ => &pauses,
+            };
+
+            self.voice_track(voice).append_notes(notes);
+        }
+    }
+
+    fn voice_track(&mut self, voice: usize) -> &mut UnboundTrack<T, U> {
+        match voice {
+            0 => &mut self.unbound_track,
+            _ => &mut self.voice_tracks[voice - 1],
+        }
+    }
 
-        for beat in beats {
-            if position == position_in_measure {
-                return *beat;
+    /// The last note placed in the given voice, which may be in a completed
+    /// measure.
+    fn last_note(&mut self, voice: usize) -> Option<&mut Note<T>> {
+        last_note_of(self.active_measure.as_mut().unwrap(), &mut self.unbound_track, &mut self.voice_tracks, voice)
+    }
+
+    /// End a dynamics or parameter change. `end` is called with the last note
+    /// of every voice the change was started in, and of the active voice, and
+    /// whether the change was started in that voice.
+    fn end_change<F>(&mut self, started_voices: &[usize], mut end: F)
+    where
+        F: FnMut(&mut Note<T>, bool),
+    {
+        let mut voices = started_voices.to_vec();
+        if !voices.contains(&self.active_voice) {
+            voices.push(self.active_voice);
+        }
+
+        for voice in voices {
+            let started = started_voices.contains(&voice);
+
+            if let Some(note) = self.last_note(voice) {
+                end(note, started);
             }
-            // Missed the beat
-            if position.to_float() < position_in_measure.to_float() {
-                return self.time_signature.offbeat_intensity();
+        }
+    }
+
+    /// The start of every measure in seconds. A pickup measure starts before
+    /// the track, at the time a full measure would start.
+    fn measure_starts(&self, bpm: f32) -> Vec<f64> {
+        let mut starts = Vec::new();
+        let mut time = Duration::ZERO;
+
+        for (index, (time_signature, lengths)) in self.completed_measures.iter().enumerate() {
+            let missing = match self.pickup {
+                Some(pickup) if index == 0 => (time_signature.measure_length - pickup).to_duration(bpm),
+                _ => Duration::ZERO,
+            };
+            starts.push(time.as_secs_f64() - missing.as_secs_f64());
+
+            for length in lengths {
+                time += length.to_duration(bpm);
             }
+        }
 
-            position_in_measure += self.time_signature.beat_length();
+        starts
+    }
+
+    /// The time signature of the first measure and every change, with the
+    /// time the measure starts at.
+    fn time_signature_changes(&self, bpm: f32) -> Vec<(Duration, TimeSignature)> {
+        let mut changes: Vec<(Duration, TimeSignature)> = Vec::new();
+        let mut time = Duration::ZERO;
+
+        for (time_signature, lengths) in &self.completed_measures {
+            let changed = changes.last()
+                .is_none_or(|(_, previous)| previous != time_signature);
+
+            if changed {
+                changes.push((time, time_signature.clone()));
+            }
+
+            for length in lengths {
+                time += length.to_duration(bpm);
+            }
         }
 
-        return self.time_signature.offbeat_intensity();
+        changes
     }
 }
 
@@ -174,43 +520,170 @@ impl<T: ScaledValue> Measure<T> {
     fn new(time_signature: TimeSignature) -> Self {
         Self {
             time_signature,
-            notes: Vec::new(),
+            voices: vec![Vec::new()],
+            active_voice: 0,
+            pickup: None,
+        }
+    }
+
+    /// The notes of the active voice
+    fn notes_mut(&mut self) -> &mut Vec<Note<T>> {
+        if self.voices.len() <= self.active_voice {
+            self.voices.resize_with(self.active_voice + 1, Vec::new);
         }
+
+        &mut self.voices[self.active_voice]
     }
 
-    fn is_empty(&self) -> bool {
-        self.notes.is_empty()
+    fn voice(&self, voice: usize) -> &[Note<T>] {
+        self.voices.get(voice).map(|notes| notes.as_slice()).unwrap_or(&[])
     }
 
-    fn assert_measure_bounds(&self) -> bool {
-        let mut all_lengths = Vec::new();
+    /// The lengths of everything before the next note in the active voice,
+    /// including the missing start of a pickup measure.
+    fn position(&self) -> Vec<Length> {
+        let mut lengths = Vec::new();
 
-        for note in &self.notes {
-            all_lengths.push(note.length);
+        if let Some(pickup) = self.pickup {
+            lengths.push(self.time_signature.measure_length - pickup);
         }
 
-        let total_length = Length::count_lengths(&all_lengths).unwrap();
+        lengths.extend(self.voice(self.active_voice).iter().map(|note| note.length));
+        lengths
+    }
+
+    /// The note lengths of every voice with notes
+    fn filled_voices(&self) -> Vec<Vec<Length>> {
+        self.voices.iter()
+            .filter(|notes| !notes.is_empty())
+            .map(|notes| notes.iter().map(|note| note.length).collect())
+            .collect()
+    }
+
+    /// Every voice with notes has to fill the measure (or the pickup), and at
+    /// least one voice needs notes.
+    fn assert_measure_bounds(&self) -> bool {
+        let voices = self.filled_voices();
 
-        return self.time_signature.is_measure_saturated(total_length);
+        !voices.is_empty() && voices.iter().all(|lengths| match self.pickup {
+            Some(pickup) => lengths.iter().copied().sum::<Length>() == pickup,
+            None => self.time_signature.is_measure_filled(lengths),
+        })
     }
 
-    fn get_total_length(&self) -> Length {
-        let mut note_lengths = Vec::new();
+    /// Every voice with notes has to complete the pickup to a full measure.
+    /// Without a pickup, all voices need the same length, which can't exceed
+    /// a full measure.
+    fn assert_final_measure_bounds(&self, pickup: Option<Length>) -> bool {
+        let voices = self.filled_voices();
 
-        for note in &self.notes {
-            note_lengths.push(note.length);
+        let Some(first_voice) = voices.first() else {
+            return false;
+        };
+
+        if let Some(pickup) = pickup {
+            return voices.iter().all(|lengths| {
+                self.time_signature.is_measure_filled(&[&[pickup], lengths.as_slice()].concat())
+            });
         }
 
-        return
-            Length::count_lengths(&note_lengths)
-            .unwrap_or(length::INVALID);
+        let length: Length = first_voice.iter().copied().sum();
+
+        length <= self.time_signature.measure_length
+            && voices.iter().all(|lengths| lengths.iter().copied().sum::<Length>() == length)
     }
 
-    /// Override the time signature for this measure.
+    /// Override the time signature for this measure only. Use
+    /// `MeasureTrack::set_time_signature()` to change it for all following
+    /// measures.
     pub fn override_time_signature(&mut self, time_signature: TimeSignature) -> &mut Self {
         self.time_signature = time_signature;
         self
     }
 }
 
+/// A pickup measure needs notes, but has to be shorter than a full measure.
+fn assert_valid_pickup(pickup: Length, time_signature: &TimeSignature) {
+    if pickup == ZERO || pickup >= time_signature.measure_length {
+        panic!("The pickup needs to be shorter than a full measure.");
+    }
+}
+
+/// The last note of a voice in the active measure, or in the track of the
+/// voice if the measure has no notes in it. Takes the fields of `MeasureTrack`
+/// separately, so the note can be borrowed next to the other fields.
+fn last_note_of<'a, T, U>(
+    active_measure: &'a mut Measure<T>,
+    unbound_track: &'a mut UnboundTrack<T, U>,
+    voice_tracks: &'a mut [UnboundTrack<T, U>],
+    voice: usize,
+) -> Option<&'a mut Note<T>>
+where
+    T: ScaledValue<ConcreteValue = U::ConcreteValue>,
+    U: Instrument,
+{
+    if active_measure.voice(voice).is_empty() {
+        return match voice {
+            0 => unbound_track.get_active_note(),
+            _ => voice_tracks[voice - 1].get_active_note(),
+        };
+    }
+
+    active_measure.voices[voice].last_mut()
+}
+
+/// Merge the export tracks of all voices into the export track of the first
+/// voice. All tones get an explicit start time, so that they can overlap.
+fn merge_voices<U: Instrument>(main: ExportTrack<U>, voices: Vec<ExportTrack<U>>) -> ExportTrack<U> {
+    let instrument = main.instrument.clone();
+
+    let mut tones = Vec::new();
+    let mut change_times: Vec<(Duration, U)> = Vec::new();
+
+    for voice in std::iter::once(main).chain(voices) {
+        let starts = start_times(&voice.tones);
+
+        // Instrument changes are placed by time, as the tones of all voices
+        // are interleaved after merging
+        for (index, instrument) in voice.instrument_changes {
+            // A change after the last tone applies at the end of the track
+            let time = match starts.get(index) {
+                Some(start) => *start,
+                None => starts.last()
+                    .zip(voice.tones.last())
+                    .map(|(start, tone)| *start + tone.play_duration)
+                    .unwrap_or(Duration::ZERO),
+            };
+            change_times.push((time, instrument));
+        }
+
+        for (mut tone, start) in voice.tones.into_iter().zip(starts) {
+            tone.start = Some(start);
+            tones.push(tone);
+        }
+    }
+
+    // Stable, so at the same start time the first voice comes first
+    tones.sort_by_key(|tone| tone.start);
+    change_times.sort_by_key(|(time, _)| *time);
+
+    let mut instrument_changes: Vec<(usize, U)> = Vec::new();
+    for (time, instrument) in change_times {
+        let index = tones.partition_point(|tone| tone.start.unwrap() < time);
+
+        // Changes of several voices at the same time replace each other
+        match instrument_changes.last_mut() {
+            Some(last_change) if last_change.0 == index => last_change.1 = instrument,
+            _ => instrument_changes.push((index, instrument)),
+        }
+    }
+
+    ExportTrack {
+        tones,
+        instrument,
+        instrument_changes,
+        time_signatures: Vec::new(),
+    }
+}
+
 mod tests;
@@ -6,6 +6,7 @@ use crate::prelude::*;
 use crate::prelude::tet12::*;
 use crate::prelude::length::*;
 use crate::prelude::predefined::SineGenerator as instrument;
+use crate::file_export::export_info::start_times;
 
 const SETTINGS: CompositionSettings = CompositionSettings {
     sample_rate: 44100,
@@ -68,12 +69,14 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 0.25),
             tone_duration: Duration::from_secs_f32(time_whole * 0.25),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(D4)],
             play_duration: Duration::from_secs_f32(time_whole * 0.5),
             tone_duration: Duration::from_secs_f32(time_whole * 0.5),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
 
         Tone {
@@ -81,24 +84,28 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 0.0625),
             tone_duration: Duration::from_secs_f32(time_whole * 0.0625),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(F4 - 12)],
             play_duration: Duration::from_secs_f32(time_whole * 0.0625),
             tone_duration: Duration::from_secs_f32(time_whole * 0.0625),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(G4 - 12 + 1)],
             play_duration: Duration::from_secs_f32(time_whole * 0.0625),
             tone_duration: Duration::from_secs_f32(time_whole * 0.0625),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(A4 - 12)],
             play_duration: Duration::from_secs_f32(time_whole * 0.0625),
             tone_duration: Duration::from_secs_f32(time_whole * 0.0625),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
 
         Tone {
@@ -111,6 +118,7 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 1.0),
             tone_duration: Duration::from_secs_f32(time_whole * 1.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
 
         Tone {
@@ -118,18 +126,21 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole / 3.0),
             tone_duration: Duration::from_secs_f32(time_whole / 3.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(E4)],
             play_duration: Duration::from_secs_f32(time_whole / 3.0),
             tone_duration: Duration::from_secs_f32(time_whole / 3.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(G4)],
             play_duration: Duration::from_secs_f32(time_whole / 3.0),
             tone_duration: Duration::from_secs_f32(time_whole / 3.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
     ];
 
@@ -320,6 +331,496 @@ fn conversion_emphasis() {
     }
 }
 
+#[test]
+fn conversion_dynamic_markings() {
+    let four_four = TimeSignature::new(4, 4)
+        .set_beat(0, 2.0)
+        .set_offbeat(0.5);
+
+    let table = DynamicsTable::new()
+        .set(Dynamic::P, 0.2)
+        .set(Dynamic::F, 0.4)
+        .set_accent(Accent::Accent, 1.5)
+        .set_accent(Accent::Sforzando, 3.0);
+
+    let mut track = MeasureTrack::new(instrument, four_four);
+    track.set_dynamics_table(table);
+
+    track.set_dynamic(Dynamic::F);
+    track.note(QUARTER, first(4)).accent();
+    track.note(EIGTH, first(4));
+    track.note(EIGTH, first(4)).accent();
+    track.set_dynamic(Dynamic::Sfz);
+    track.note(HALF, first(4));
+    track.measure().unwrap();
+
+    track.set_dynamic(Dynamic::Fp);
+    track.note(HALF, first(4));
+    track.note(HALF, first(4));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    // Accents are combined with the beat emphasis
+    let expected_intensity = vec![
+        0.4 * 2.0 * 1.5,
+        0.4,
+        0.4 * 0.5 * 1.5,
+        0.4 * 3.0,
+        0.4 * 2.0,
+        0.2,
+    ];
+
+    for (tone, expected) in result.iter().zip(expected_intensity) {
+        assert_eq_f32(tone.intensity.start, expected, 0.0001);
+    }
+
+    // Fortepiano falls to piano within the note
+    assert_eq_f32(result[4].intensity.end, 0.2 * 2.0, 0.0001);
+    let end = result[4].play_duration;
+    assert_eq_f32(result[4].intensity_at(end), 0.2 * 2.0, 0.0001);
+    assert_eq_f32(result[5].intensity.end, 0.2, 0.0001);
+}
+
+#[test]
+fn slur_across_measures() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+
+    track.start_slur();
+    track.note(WHOLE, first(4));
+    track.measure().unwrap();
+    track.note(WHOLE, second(4));
+    track.end_slur();
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    assert!(result[0].tone_duration > result[0].play_duration);
+    assert_eq!(result[1].slurred_from, Some(vec![TET12ConcreteTone(C4)]));
+}
+
+#[test]
+fn ties_across_measures() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_intensity(1.0);
+
+    track.note(HALF, first(4));
+    track.note(HALF, second(4)).tie();
+    track.measure().unwrap();
+
+    track.note(QUARTER, second(4));
+    track.note(HALF.dot(), third(4));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+    let time_whole = 2.0;
+
+    let expected_tones = vec![
+        Tone {
+            concrete_values: vec![TET12ConcreteTone(C4)],
+            play_duration: Duration::from_secs_f32(time_whole * 0.5),
+            tone_duration: Duration::from_secs_f32(time_whole * 0.5),
+            intensity: 1.0..1.0,
+            ..Default::default()
+        },
+        Tone {
+            concrete_values: vec![TET12ConcreteTone(D4)],
+            play_duration: Duration::from_secs_f32(time_whole * 0.75),
+            tone_duration: Duration::from_secs_f32(time_whole * 0.75),
+            intensity: 1.0..1.0,
+            ..Default::default()
+        },
+        Tone {
+            concrete_values: vec![TET12ConcreteTone(E4)],
+            play_duration: Duration::from_secs_f32(time_whole * 0.75),
+            tone_duration: Duration::from_secs_f32(time_whole * 0.75),
+            intensity: 1.0..1.0,
+            ..Default::default()
+        },
+    ];
+
+    assert_eq_tones(&result, &expected_tones);
+}
+
+#[test]
+fn conversion_parameters() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+
+    track.set_parameter(parameter::BRIGHTNESS, 0.0);
+    track.start_parameter_change(parameter::BRIGHTNESS);
+    sequential_notes!(track, HALF, first(4), first(4));
+    track.measure().unwrap();
+
+    sequential_notes!(track, HALF, first(4), first(4));
+    track.end_parameter_change(parameter::BRIGHTNESS, 1.0);
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+    let brightness: Vec<_> = result.iter()
+        .map(|tone| tone.parameters.get(parameter::BRIGHTNESS).cloned().unwrap())
+        .collect();
+
+    assert_eq!(brightness, vec![0.0..0.25, 0.25..0.5, 0.5..0.75, 0.75..1.0]);
+}
+
+#[test]
+fn instrument_changes() {
+    let mut track = MeasureTrack::new(Tagged(0), TimeSignature::new(4, 4));
+
+    sequential_notes!(track, HALF, first(4), second(4));
+    track.measure().unwrap();
+
+    track.note(HALF, third(4));
+    // Changing within the active measure
+    track.set_instrument(Tagged(1));
+    track.note(HALF, fourth(4));
+    track.measure().unwrap();
+
+    // Changing at the start of a new measure
+    track.set_instrument(Tagged(2));
+    track.note(WHOLE, fifth(4));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    assert_eq!(result.instrument, Tagged(0));
+    assert_eq!(result.instrument_changes, vec![(3, Tagged(1)), (4, Tagged(2))]);
+}
+
+#[test]
+fn voices_validated_separately() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+
+    track.set_voice(0);
+    track.note(WHOLE, first(4));
+    track.set_voice(1);
+    track.note(HALF, first(3));
+
+    assert!(track.measure().is_err());
+
+    track.note(HALF, fifth(2));
+    track.measure().unwrap();
+
+    // A voice without notes is filled with pauses
+    track.set_voice(0);
+    track.note(HALF, second(4));
+    track.note(HALF, third(4));
+    track.measure().unwrap();
+}
+
+#[test]
+fn voices_merged() {
+    let mut track = MeasureTrack::new(Tagged(0), TimeSignature::new(4, 4));
+    track.set_intensity(1.0);
+    track.set_play_fraction(1.0);
+
+    track.note(QUARTER, first(4));
+    track.note(HALF, second(4));
+    track.set_instrument(Tagged(1));
+    track.note(QUARTER, third(4));
+
+    track.set_voice(1);
+    track.note(WHOLE, first(3));
+    track.measure().unwrap();
+
+    // Voice 1 is filled with a pause in this measure
+    track.set_voice(0);
+    track.note(WHOLE, fourth(4));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    let starts: Vec<(Option<Duration>, Vec<TET12ConcreteTone>)> = result.tones.iter()
+        .map(|tone| (tone.start, tone.concrete_values.clone()))
+        .collect();
+
+    let seconds = |value: f32| Some(Duration::from_secs_f32(value));
+    assert_eq!(starts, vec![
+        (seconds(0.0), vec![TET12ConcreteTone(C4)]),
+        (seconds(0.0), vec![TET12ConcreteTone(C4 - 12)]),
+        (seconds(0.5), vec![TET12ConcreteTone(D4)]),
+        (seconds(1.5), vec![TET12ConcreteTone(E4)]),
+        (seconds(2.0), vec![TET12ConcreteTone(F4)]),
+        (seconds(2.0), vec![]),
+    ]);
+
+    assert_eq!(result.instrument_changes, vec![(3, Tagged(1))]);
+}
+
+#[test]
+fn dynamics_across_voices() {
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    // A change over a single note in the voice it was started in
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.start_dynamic_change();
+    track.note(WHOLE, first(4));
+    track.set_voice(1);
+    track.note(WHOLE, first(3));
+    track.end_dynamic_change(1.0);
+    track.measure().unwrap();
+    track.convert_to_export_track(info);
+
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_intensity(0.2);
+    track.set_parameter(parameter::BRIGHTNESS, 0.0);
+    track.start_dynamic_change();
+    track.start_parameter_change(parameter::BRIGHTNESS);
+    sequential_notes!(track, HALF, first(4), second(4));
+
+    track.set_voice(1);
+    track.note(WHOLE, first(3));
+    track.end_dynamic_change(1.0);
+    track.end_parameter_change(parameter::BRIGHTNESS, 1.0);
+    track.measure().unwrap();
+
+    let result = track.convert_to_export_track(info).tones;
+
+    let intensities: Vec<_> = result.iter().map(|tone| tone.intensity.clone()).collect();
+    assert_eq!(intensities, vec![0.2..0.6, 1.0..1.0, 0.6..1.0]);
+
+    let brightness: Vec<_> = result.iter()
+        .map(|tone| tone.parameters.get(parameter::BRIGHTNESS).cloned().unwrap())
+        .collect();
+    assert_eq!(brightness, vec![0.0..0.5, 1.0..1.0, 0.5..1.0]);
+}
+
+#[test]
+fn instrument_change_in_voice() {
+    let mut track = MeasureTrack::new(Tagged(0), TimeSignature::new(4, 4));
+
+    track.note(WHOLE, first(4));
+    track.set_voice(1);
+    track.note(HALF, first(3));
+    track.set_instrument(Tagged(1));
+    track.note(HALF, second(3));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    assert_eq!(result.instrument, Tagged(0));
+    assert_eq!(result.instrument_changes, vec![(2, Tagged(1))]);
+}
+
+#[test]
+fn instrument_change_in_voice_with_other_rhythm() {
+    let mut track = MeasureTrack::new(Tagged(0), TimeSignature::new(4, 4));
+
+    sequential_notes!(track, QUARTER, first(4), second(4), third(4), fourth(4));
+    track.set_voice(1);
+    track.note(HALF.dot(), first(3));
+    track.set_instrument(Tagged(1));
+    track.note(QUARTER, second(3));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    // The instrument of every tone, in the order that `render()` uses
+    let mut active_instrument = result.instrument;
+    let mut changes = result.instrument_changes.iter().peekable();
+    let mut rendered = Vec::new();
+
+    for (i, (tone, start)) in result.tones.iter().zip(start_times(&result.tones)).enumerate() {
+        while let Some((_, next)) = changes.next_if(|(index, _)| *index <= i) {
+            active_instrument = *next;
+        }
+
+        assert_eq!(tone.start, Some(start));
+        rendered.push((start, tone.concrete_values.clone(), active_instrument));
+    }
+
+    let seconds = |value: f32| Duration::from_secs_f32(value);
+    assert_eq!(rendered, vec![
+        (seconds(0.0), vec![TET12ConcreteTone(C4)], Tagged(0)),
+        (seconds(0.0), vec![TET12ConcreteTone(C4 - 12)], Tagged(0)),
+        (seconds(0.5), vec![TET12ConcreteTone(D4)], Tagged(0)),
+        (seconds(1.0), vec![TET12ConcreteTone(E4)], Tagged(0)),
+        (seconds(1.5), vec![TET12ConcreteTone(F4)], Tagged(1)),
+        (seconds(1.5), vec![TET12ConcreteTone(D4 - 12)], Tagged(1)),
+    ]);
+}
+
+#[test]
+fn time_signature_changes() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_intensity(1.0);
+
+    track.note(WHOLE, first(4));
+    track.measure().unwrap();
+
+    track.set_time_signature(TimeSignature::new(3, 4).set_beat(0, 2.0).set_offbeat(0.5));
+    track.note(WHOLE, first(4));
+    assert!(track.measure().is_err());
+
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_intensity(1.0);
+    track.note(WHOLE, first(4));
+    track.measure().unwrap();
+
+    let three_four = TimeSignature::new(3, 4).set_beat(0, 2.0).set_offbeat(0.5);
+    track.set_time_signature(three_four.clone());
+    track.note(QUARTER, first(4));
+    track.note(EIGTH, second(4));
+    track.note(EIGTH, third(4));
+    track.note(QUARTER, fourth(4));
+    track.measure().unwrap();
+
+    track.note(HALF.dot(), fifth(4));
+    track.measure().unwrap();
+
+    track.set_time_signature(TimeSignature::new(4, 4));
+    track.note(WHOLE, first(4));
+    track.measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    let intensities: Vec<f32> = result.tones.iter()
+        .map(|tone| tone.intensity.start)
+        .collect();
+    assert_eq!(intensities, vec![1.0, 2.0, 1.0, 0.5, 1.0, 2.0, 1.0]);
+
+    assert_eq!(result.time_signatures, vec![
+        (Duration::ZERO, TimeSignature::new(4, 4)),
+        (Duration::from_secs(2), three_four),
+        (Duration::from_secs(5), TimeSignature::new(4, 4)),
+    ]);
+}
+
+#[test]
+fn pickup_measure() {
+    let three_four = TimeSignature::new(3, 4)
+        .set_beat(0, 2.0)
+        .set_offbeat(0.5);
+
+    let mut track = MeasureTrack::new(instrument, three_four);
+    track.set_intensity(1.0);
+
+    track.set_pickup(QUARTER);
+    track.note(EIGTH, fifth(3));
+    assert!(track.measure().is_err());
+    track.note(EIGTH, sixth(3));
+    track.measure().unwrap();
+
+    sequential_notes!(track, QUARTER, first(4), first(4), second(4));
+    track.measure().unwrap();
+
+    track.note(QUARTER, first(4));
+    assert!(track.final_measure().is_err());
+    track.note(QUARTER, second(4));
+    track.final_measure().unwrap();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    // The pickup starts on the last beat of the measure
+    let intensities: Vec<f32> = result.tones.iter()
+        .map(|tone| tone.intensity.start)
+        .collect();
+    assert_eq!(intensities, vec![1.0, 0.5, 2.0, 1.0, 1.0, 2.0, 1.0]);
+
+    assert_eq!(result.time_signatures.len(), 1);
+}
+
+#[test]
+#[should_panic]
+fn pickup_longer_than_measure() {
+    let mut track: MeasureTrack<TET12ScaledTone, _> = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_pickup(WHOLE * 2);
+}
+
+#[test]
+#[should_panic]
+fn empty_pickup() {
+    let mut track: MeasureTrack<TET12ScaledTone, _> = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_pickup(ZERO);
+}
+
+#[test]
+#[should_panic]
+fn pickup_longer_than_new_time_signature() {
+    let mut track: MeasureTrack<TET12ScaledTone, _> = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+    track.set_pickup(HALF.dot());
+    track.set_time_signature(TimeSignature::new(2, 4));
+}
+
+#[test]
+fn final_measure_without_pickup() {
+    let mut track = MeasureTrack::new(instrument, TimeSignature::new(4, 4));
+
+    track.note(WHOLE, first(4));
+    track.measure().unwrap();
+
+    track.note(HALF.dot(), first(4));
+    track.final_measure().unwrap();
+
+    track.note(WHOLE, first(4));
+    track.note(QUARTER, first(4));
+    assert!(track.final_measure().is_err());
+}
+
 // Utility functions
 
 fn assert_eq_tones<T>(a: &Vec<Tone<T>>, b: &Vec<Tone<T>>)
@@ -355,3 +856,10 @@ fn assert_eq_f32(a: f32, b: f32, epsilon: f32) {
         panic!("assertion failed: {} != {}", a, b);
     }
 }
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Tagged(u8);
+
+impl Instrument for Tagged {
+    type ConcreteValue = TET12ConcreteTone;
+}
@@ -103,7 +103,7 @@ pub const B_MINOR: MusicKey = MusicKey {
 };
 
 /// The tonic of a music key
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum KeyTonic {
     B,
     Bflat,
@@ -130,14 +130,101 @@ pub enum KeyTonic {
     C,
 }
 
-/// The type of a music key
+/// The type of a music key, which is the scale that scale degrees refer to.
 /// 
 /// In music theory, this is usually inferred from the melody. In this crate
 /// it does affect the notes, so it must be specified.
-#[derive(Clone, Copy)]
+/// 
+/// Besides major and (natural) minor, there are the church modes, harmonic and
+/// melodic minor, and a few scales with fewer degrees. For scales with fewer
+/// than seven degrees, the higher degrees continue in the next octave, e.g. the
+/// sixth degree of a pentatonic scale is the first degree an octave higher.
+/// 
+/// Custom scales are defined by the distance of every degree from the tonic in
+/// semitones, starting with `0`:
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// 
+/// // Hirajōshi scale
+/// const HIRAJOSHI: KeyType = KeyType::Custom(&[0, 2, 3, 7, 8]);
+/// 
+/// let key = MusicKey {
+///     tonic: KeyTonic::E,
+///     key_type: HIRAJOSHI,
+/// };
+/// 
+/// assert_eq!(HIRAJOSHI.degrees(), 5);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum KeyType {
     Major,
+    /// Natural minor
     Minor,
+
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+
+    HarmonicMinor,
+    /// Ascending melodic minor
+    MelodicMinor,
+
+    PentatonicMajor,
+    PentatonicMinor,
+    Blues,
+    WholeTone,
+
+    /// The distance of every degree from the tonic in semitones.
+    Custom(&'static [i32]),
+}
+
+impl KeyType {
+    /// The distance of every degree of the scale from the tonic in semitones.
+    pub fn intervals(&self) -> &'static [i32] {
+        match self {
+            Self::Major           => &[0, 2, 4, 5, 7, 9, 11],
+            Self::Minor           => &[0, 2, 3, 5, 7, 8, 10],
+
+            Self::Dorian          => &[0, 2, 3, 5, 7, 9, 10],
+            Self::Phrygian        => &[0, 1, 3, 5, 7, 8, 10],
+            Self::Lydian          => &[0, 2, 4, 6, 7, 9, 11],
+            Self::Mixolydian      => &[0, 2, 4, 5, 7, 9, 10],
+            Self::Locrian         => &[0, 1, 3, 5, 6, 8, 10],
+
+            Self::HarmonicMinor   => &[0, 2, 3, 5, 7, 8, 11],
+            Self::MelodicMinor    => &[0, 2, 3, 5, 7, 9, 11],
+
+            Self::PentatonicMajor => &[0, 2, 4, 7, 9],
+            Self::PentatonicMinor => &[0, 3, 5, 7, 10],
+            Self::Blues           => &[0, 3, 5, 6, 7, 10],
+            Self::WholeTone       => &[0, 2, 4, 6, 8, 10],
+
+            Self::Custom(intervals) => intervals,
+        }
+    }
+
+    /// The number of degrees of the scale in one octave.
+    pub fn degrees(&self) -> usize {
+        self.intervals().len()
+    }
+
+    /// The distance of a degree (starting at `0` for the tonic) from the tonic
+    /// in semitones. Degrees beyond the scale continue in the next octaves.
+    /// 
+    /// Panics if the scale has no degrees.
+    pub fn distance(&self, index: usize) -> i32 {
+        let intervals = self.intervals();
+
+        if intervals.is_empty() {
+            panic!("The scale needs at least one degree");
+        }
+
+        let octave = (index / intervals.len()) as i32;
+        intervals[index % intervals.len()] + 12 * octave
+    }
 }
 
 /// Represents a key in music theory.
@@ -146,8 +233,8 @@ pub enum KeyType {
 /// only makes sense for the 12-TET note system, or partially for other similar
 /// systems.
 /// 
-/// `MusicKey` also stores whether the key is Major or Minor.
-#[derive(Clone, Copy)]
+/// `MusicKey` also stores the scale of the key, like Major or Minor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct MusicKey {
     pub tonic: KeyTonic,
     pub key_type: KeyType,
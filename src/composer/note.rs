@@ -1,8 +1,13 @@
 use super::MusicKey;
+use super::parameter::{Parameter, ParameterMap};
+use super::dynamics::{DynamicsCurve, Accent};
 
 pub mod length;
+pub mod articulation;
 #[doc(inline)]
 pub use length::Length;
+#[doc(inline)]
+pub use articulation::Articulation;
 
 /// Represents an abstract note value that is dependent on the music key. Take
 /// a look at `tet12` for an example of how this might look like.
@@ -16,7 +21,7 @@ pub trait ScaledValue: Clone {
     fn to_concrete_value(&self, key: MusicKey) -> Self::ConcreteValue;
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum DynamicsFlag {
     None,
     StartChange,
@@ -33,10 +38,16 @@ pub enum DynamicsFlag {
 /// A note has a specific `Length` that it takes up on the Track, and a
 /// `play_fraction`, which is the percentage for how long the note is being
 /// played inside the length duration. A large value (e.g. 0.95) is a held note
-/// while a small value (e.g. 0.1) is a shortly played note (staccato).
+/// while a small value (e.g. 0.1) is a shortly played note (staccato). The
+/// `articulation` sets the play fraction for common cases. A `slurred` note is
+/// connected to the previous note. A `tied` note is merged with the next note
+/// into a single tone.
 /// 
 /// The note also stores info about dynamics like the `intensity` or in which
-/// state of a dynamics change it is.
+/// state of a dynamics change it is. An `accent` multiplies the intensity of
+/// the single note, and `attack_intensity` lets the note start at a different
+/// intensity and quickly fall to `intensity` (fortepiano). The same applies to
+/// instrument `parameters`, which can be changed over multiple notes as well.
 /// 
 /// A `Note` is converted into a `Tone` in the export stage.
 #[derive(Clone)]
@@ -44,9 +55,18 @@ pub struct Note<T: ScaledValue> {
     pub values: Vec<T>,
     pub length: Length,
     pub play_fraction: f32,
+    pub articulation: Option<Articulation>,
+    pub slurred: bool,
+    pub tied: bool,
 
     pub intensity: f32,
     pub dynamics_flag: DynamicsFlag,
+    pub dynamics_curve: DynamicsCurve,
+    pub accent: Option<Accent>,
+    pub attack_intensity: Option<f32>,
+
+    pub parameters: ParameterMap<f32>,
+    pub parameter_flags: ParameterMap<DynamicsFlag>,
 }
 
 impl<T: ScaledValue> Default for Note<T> {
@@ -55,27 +75,119 @@ impl<T: ScaledValue> Default for Note<T> {
             values: Vec::new(),
             length: length::QUARTER,
             play_fraction: 1.0,
+            articulation: None,
+            slurred: false,
+            tied: false,
             
             intensity: 1.0,
             dynamics_flag: DynamicsFlag::None,
+            dynamics_curve: DynamicsCurve::Linear,
+            accent: None,
+            attack_intensity: None,
+
+            parameters: ParameterMap::new(),
+            parameter_flags: ParameterMap::new(),
         }
     }
 }
 
 impl<T: ScaledValue> Note<T> {
+    /// Tie the note to the next note placed on the track, so both are played
+    /// as one tone without retriggering. Ties can cross measure boundaries,
+    /// and can be used for lengths that can't be expressed with a single
+    /// `Length`. A tie only merges notes with the same values, so a tie into a
+    /// pause or into a different pitch is ignored.
+    /// 
+    /// ```
+    /// use synth_music::prelude::*;
+    /// use tet12::*;
+    /// use length::*;
+    /// 
+    /// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+    /// 
+    /// track.note(HALF, first(4));
+    /// track.note(QUARTER, second(4)).tie();
+    /// track.note(SIXTEENTH, second(4));
+    /// sequential_notes!(track, SIXTEENTH, third(4), fourth(4), fifth(4));
+    /// track.measure().unwrap();
+    /// 
+    /// // Held over the barline
+    /// track.note(HALF, first(4));
+    /// track.note(HALF, fifth(4)).tie();
+    /// track.measure().unwrap();
+    /// track.note(WHOLE, fifth(4));
+    /// track.measure().unwrap();
+    /// ```
+    pub fn tie(&mut self) -> &mut Self {
+        self.tied = true;
+        self
+    }
+
+    /// Set the articulation of the note, which sets the `play_fraction` and
+    /// may add an accent.
+    pub fn articulate(&mut self, articulation: Articulation) -> &mut Self {
+        self.articulation = Some(articulation);
+        self.play_fraction = articulation.play_fraction();
+
+        if let Some(accent) = articulation.accent() {
+            self.accent = Some(accent);
+        }
+
+        self
+    }
+
     /// Makes the `play_fraction` short so that the note is only played for a
     /// small fraction of it's length.
     pub fn staccato(&mut self) -> &mut Self {
-        self.play_fraction = 0.2;
+        self.articulate(Articulation::Staccato)
+    }
+
+    /// Play the note even shorter than `staccato()`, with a slight accent.
+    pub fn staccatissimo(&mut self) -> &mut Self {
+        self.articulate(Articulation::Staccatissimo)
+    }
+
+    /// Play the note slightly detached.
+    pub fn portato(&mut self) -> &mut Self {
+        self.articulate(Articulation::Portato)
+    }
+
+    /// Hold the note for its full length.
+    pub fn tenuto(&mut self) -> &mut Self {
+        self.articulate(Articulation::Tenuto)
+    }
+
+    /// Hold the note a bit longer than its length, so it overlaps with the
+    /// next note. To connect a group of notes, use a slur on the track.
+    pub fn legato(&mut self) -> &mut Self {
+        self.articulate(Articulation::Legato)
+    }
+
+    /// Play the note louder.
+    pub fn accent(&mut self) -> &mut Self {
+        self.accent = Some(Accent::Accent);
+        self
+    }
+
+    /// Play the note shorter and a lot louder than an `accent()`.
+    pub fn marcato(&mut self) -> &mut Self {
+        self.articulate(Articulation::Marcato)
+    }
+
+    /// Play the note with a sudden, strong accent.
+    pub fn sforzando(&mut self) -> &mut Self {
+        self.accent = Some(Accent::Sforzando);
+        self
+    }
+
+    /// Set an instrument parameter only for this note.
+    pub fn set_parameter(&mut self, key: Parameter, value: f32) -> &mut Self {
+        self.parameters.set(key, value);
         self
     }
 
     /// Get a duration for the note length given the tempo.
     pub fn get_duration(&self, bpm: f32) -> std::time::Duration {
-        let quarters_per_second = bpm / 60.0;
-        let note_length = self.length.to_float();
-        let time = (4.0 * note_length) / quarters_per_second;
-
-        return std::time::Duration::from_secs_f32(time);
+        self.length.to_duration(bpm)
     }
 }
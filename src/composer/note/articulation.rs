@@ -0,0 +1,63 @@
+use crate::composer::dynamics::Accent;
+
+/// How long a legato note is played relative to its length. Values above
+/// `1.0` let the note overlap with the next one.
+pub const LEGATO_PLAY_FRACTION: f32 = 1.1;
+
+/// The way a note is played. An articulation sets the play fraction of the
+/// note, and may add an accent to its attack.
+///
+/// Instruments can read the articulation from `Tone` to change the attack of
+/// the sound, e.g. a softer attack for legato notes.
+///
+/// | Articulation    | Play fraction | Accent    |
+/// |-----------------|---------------|-----------|
+/// | `Staccatissimo` | 0.1           | `Accent`  |
+/// | `Staccato`      | 0.2           |           |
+/// | `Portato`       | 0.75          |           |
+/// | `Tenuto`        | 1.0           |           |
+/// | `Legato`        | 1.1 (overlap) |           |
+/// | `Marcato`       | 0.8           | `Marcato` |
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+///
+/// let mut track = UnboundTrack::new(predefined::SineGenerator);
+///
+/// track.note(QUARTER, first(4)).staccato();
+/// track.note(QUARTER, second(4)).portato();
+/// track.note(QUARTER, third(4)).tenuto();
+/// track.note(QUARTER, fourth(4)).articulate(Articulation::Staccatissimo);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Articulation {
+    Staccatissimo,
+    Staccato,
+    Portato,
+    Tenuto,
+    Legato,
+    Marcato,
+}
+
+impl Articulation {
+    pub fn play_fraction(self) -> f32 {
+        match self {
+            Self::Staccatissimo => 0.1,
+            Self::Staccato => 0.2,
+            Self::Portato => 0.75,
+            Self::Tenuto => 1.0,
+            Self::Legato => LEGATO_PLAY_FRACTION,
+            Self::Marcato => 0.8,
+        }
+    }
+
+    pub fn accent(self) -> Option<Accent> {
+        match self {
+            Self::Staccatissimo => Some(Accent::Accent),
+            Self::Marcato => Some(Accent::Marcato),
+            _ => None,
+        }
+    }
+}
@@ -1,4 +1,3 @@
-pub const INVALID:   Length = Length::from_ticks(u32::MAX);
 pub const ZERO:      Length = Length::from_ticks(0);
 
 pub const WHOLE:     Length = Length::from_subdivisions(0);
@@ -12,6 +11,10 @@ const TICKS_WHOLE: u32 = 2_u32.pow(16);
 /// Represents a note length. It can take many forms such as dotted notes and
 /// also n-toles.
 /// 
+/// Lengths are stored as an exact fraction of a whole note, so lengths of
+/// different tuplets (and nested tuplets) can be added, subtracted and
+/// compared without losing precision.
+/// 
 /// It's best to use the provided constants, it should only rarely be necessary
 /// to use the constructors.
 /// 
@@ -32,20 +35,34 @@ const TICKS_WHOLE: u32 = 2_u32.pow(16);
 /// 
 /// track.note(EIGTH.dot(), third(3));
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Length {
-    // Number of 65536th values
-    ticks: u32,
-    ntole_index: u8,
+    // Fraction of a whole note, always reduced
+    numerator: u64,
+    denominator: u64,
 }
 
 impl Length {
     /// Construct a length from internally used "ticks". A "tick" is a 65536th
     /// note, and therefore the shortest representable note.
     pub const fn from_ticks(ticks: u32) -> Self {
+        Self::from_fraction(ticks as u64, TICKS_WHOLE as u64)
+    }
+
+    /// Construct a length as a fraction of a whole note, e.g. `(3, 8)` for
+    /// a dotted quarter.
+    /// 
+    /// Panics if the denominator is zero.
+    pub const fn from_fraction(numerator: u64, denominator: u64) -> Self {
+        if denominator == 0 {
+            panic!("The denominator of a length can't be zero");
+        }
+
+        let divisor = gcd(numerator as u128, denominator as u128) as u64;
+
         Self {
-            ticks,
-            ntole_index: 0,
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
         }
     }
 
@@ -55,8 +72,7 @@ impl Length {
     /// A subdivision of `0` is a whole note, `1` is half, `2` is a quarter,
     /// etc...
     pub const fn from_subdivisions(subdivision: u32) -> Self {
-        let ticks = TICKS_WHOLE / 2_u32.pow(subdivision);
-        Self::from_ticks(ticks)
+        Self::from_fraction(1, 2_u64.pow(subdivision))
     }
 
     /// Extend the note length by half of its value. Represents a "dot" after
@@ -65,26 +81,17 @@ impl Length {
     /// For multiple dots, `multi_dot()` will have the same behaviour as in
     /// music theory. Refrain from calling `dot()` multiple times for
     /// this.
-    pub const fn dot(mut self) -> Self {
-        self.ticks += self.ticks / 2;        
-        self
+    pub const fn dot(self) -> Self {
+        self.multi_dot(1)
     }
 
     /// Place a specified amount of dots on a note. Every dot will extend the
     /// length by half of the previous extention. E.g. two dots on a whole note
     /// will extend it by a half and a quarter note.
-    pub const fn multi_dot(mut self, dots: usize) -> Self {
-        let mut i = 0;
-        let mut dot_value = self.ticks;
-
-        while i < dots {
-            dot_value /= 2;
-            self.ticks += dot_value;
-
-            i += 1;
-        }
-
-        self
+    pub const fn multi_dot(self, dots: usize) -> Self {
+        // The length is multiplied by (2^(dots+1) - 1) / 2^dots
+        let power = 2_u64.pow(dots as u32);
+        self.scale(2 * power - 1, power)
     }
 
     /// Mark the note length as being inside a triole. There should be two other
@@ -105,133 +112,163 @@ impl Length {
     /// All note lengths will combine to the double of the base length; e.g.
     /// n notes in a quarter triole are equivalent to a half note length.
     /// 
-    /// Currently, only odd values for n are accepted (3, 5, 7, etc...)
-    pub const fn ntole(mut self, n: usize) -> Self {
-        if n % 2 == 0 {
+    /// This is the same as `tuplet(n, 2)`.
+    pub const fn ntole(self, n: usize) -> Self {
+        self.tuplet(n as u64, 2)
+    }
+
+    /// Mark the note length as being inside a tuplet, where `n` notes take the
+    /// time of `m` notes of the base length. E.g. a quintuplet of sixteenths
+    /// in the time of a quarter is `SIXTEENTH.tuplet(5, 4)`.
+    /// 
+    /// Tuplets can be nested by calling this multiple times.
+    /// 
+    /// ```
+    /// # use synth_music::prelude::*;
+    /// # use length::*;
+    /// assert_eq!(SIXTEENTH.tuplet(5, 4) * 5, QUARTER);
+    /// assert_eq!(EIGTH.tuplet(7, 8) * 7, WHOLE);
+    /// 
+    /// // A triole inside of a quintuplet
+    /// let nested = SIXTEENTH.tuplet(5, 4).triole();
+    /// assert_eq!(nested * 3, SIXTEENTH.tuplet(5, 4) * 2);
+    /// ```
+    /// 
+    /// Panics if `n` is zero.
+    pub const fn tuplet(self, n: u64, m: u64) -> Self {
+        if n == 0 {
             panic!("Invalid n-tole");
         }
 
-        let x = ((n - 1) / 2) as u8;
-
-        self.ntole_index = x;
-        self
+        self.scale(m, n)
     }
 
     /// Convert the note length into a float. This can be imprecise and
     /// shouldn't be used for comparing note lenghts (with `==` or `!=`).
     pub fn to_float(&self) -> f32 {
-        let base_length = self.ticks as f32 / TICKS_WHOLE as f32;
+        (self.numerator as f64 / self.denominator as f64) as f32
+    }
 
-        let ntole_multiplier = if self.ntole_index == 0 {
-            1.0
-        } else {
-            2.0 / Self::ntole_parts_from_index(self.ntole_index) as f32
-        };
+    /// Get a duration for the note length given the tempo.
+    pub fn to_duration(&self, bpm: f32) -> std::time::Duration {
+        let quarters_per_second = bpm / 60.0;
+        let note_length = self.to_float();
+        let time = (4.0 * note_length) / quarters_per_second;
 
-        return base_length * ntole_multiplier;
+        return std::time::Duration::from_secs_f32(time);
+    }
+
+    /// The exact length as a reduced fraction `(numerator, denominator)` of a
+    /// whole note.
+    pub fn as_fraction(&self) -> (u64, u64) {
+        (self.numerator, self.denominator)
     }
 
     /// For precicesly combining multiple note lengths into one.
     /// 
-    /// Will return an error if there are incomplete n-toles.
-    pub fn count_lengths(lengths: &Vec<Self>) -> Result<Self, &str> {
-        let mut total_length = ZERO;
-
-        let mut ntole_count: Vec<(Self, u16)> = Vec::new();
-
-        for length in lengths {
-            if length.ntole_index == 0 {
-                total_length.ticks += length.ticks;
-            }
-            else {
-                total_length.ticks += Self::count_ntole(&mut ntole_count, *length);
-            }
-        }
-
-        if ! ntole_count.is_empty() {
-            return Err("Not all ntoles have simplified");
-        }
-
-        Ok(total_length)
+    /// It's the same as summing the lengths, but returns an error instead of
+    /// panicking if the total can't be represented, e.g. after adding many
+    /// different nested tuplets.
+    pub fn count_lengths(lengths: &[Self]) -> Result<Self, &str> {
+        lengths.iter()
+            .try_fold(ZERO, |total, length| total.checked_add(*length))
+            .ok_or("The total length can't be represented")
     }
 
-    fn ntole_parts_from_index(ntole_index: u8) -> u16 {
-        ntole_index as u16 * 2 + 1
+    /// Add two lengths, or return `None` if the exact sum can't be
+    /// represented.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (a, b, denominator) = self.common_denominator(other);
+        Self::from_wide_fraction(a.checked_add(b)?, denominator)
     }
 
-    fn count_ntole(ntole_count: &mut Vec<(Self, u16)>, length: Self) -> u32 {
-        // Search if ntole index exists in vector
-        for i in 0..ntole_count.len() {
-            if ntole_count[i].0 != length {
-                continue;
-            }
+    /// Subtract a length, or return `None` if the result would be negative or
+    /// can't be represented.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (a, b, denominator) = self.common_denominator(other);
+        Self::from_wide_fraction(a.checked_sub(b)?, denominator)
+    }
 
-            // It does exist
+    /// Panics if the result can't be represented.
+    const fn scale(self, numerator: u64, denominator: u64) -> Self {
+        let result = Self::from_wide_fraction(
+            self.numerator as u128 * numerator as u128,
+            self.denominator as u128 * denominator as u128,
+        );
 
-            ntole_count[i].1 -= 1;
+        match result {
+            Some(length) => length,
+            None => panic!("The length is too precise to be represented"),
+        }
+    }
 
-            if ntole_count[i].1 > 0 {
-                return 0;
-            }
+    /// Reduce a fraction that may not fit into the stored values, or return
+    /// `None` if it still doesn't fit after reducing.
+    const fn from_wide_fraction(numerator: u128, denominator: u128) -> Option<Self> {
+        let divisor = gcd(numerator, denominator);
+        let numerator = numerator / divisor;
+        let denominator = denominator / divisor;
 
-            // ntole simplifies to note
-            ntole_count.remove(i);
-            return length.ticks * 2;
+        if numerator > u64::MAX as u128 || denominator > u64::MAX as u128 {
+            return None;
         }
 
-        let expected_ntole_parts = Self::ntole_parts_from_index(length.ntole_index);
-        ntole_count.push((length, expected_ntole_parts - 1));
+        Some(Self {
+            numerator: numerator as u64,
+            denominator: denominator as u64,
+        })
+    }
 
-        return 0;
+    /// Both numerators over the least common denominator, and the denominator.
+    /// The values are widened, so they can't overflow.
+    fn common_denominator(self, other: Self) -> (u128, u128, u128) {
+        let divisor = gcd(self.denominator as u128, other.denominator as u128);
+        let denominator = self.denominator as u128 / divisor * other.denominator as u128;
+
+        (
+            self.numerator as u128 * (denominator / self.denominator as u128),
+            other.numerator as u128 * (denominator / other.denominator as u128),
+            denominator,
+        )
     }
 }
 
 impl std::ops::Add for Length {
     type Output = Length;
 
+    /// Panics if the sum can't be represented, see `checked_add()`.
     fn add(self, rhs: Self) -> Self::Output {
-        if self.ntole_index != rhs.ntole_index {
-            panic!("Adding two different ntoles is not supported.");
-        }
-
-        Self {
-            ticks: self.ticks + rhs.ticks,
-            ntole_index: self.ntole_index,
+        match self.checked_add(rhs) {
+            Some(length) => length,
+            None => panic!("The sum of the lengths is too precise to be represented"),
         }
     }
 }
 impl std::ops::AddAssign for Length {
     fn add_assign(&mut self, rhs: Self) {
-        if self.ntole_index != rhs.ntole_index {
-            panic!("Adding two different ntoles is not supported.");
-        }
-
-        self.ticks += rhs.ticks;
+        *self = *self + rhs;
     }
 }
 
 impl std::ops::Sub for Length {
     type Output = Length;
 
+    /// Panics if the result would be negative.
     fn sub(self, rhs: Self) -> Self::Output {
-        if self.ntole_index != rhs.ntole_index {
-            panic!("Subtracting two different ntoles is not supported.");
+        if rhs > self {
+            panic!("Subtracting a longer length is not supported.");
         }
 
-        Self {
-            ticks: self.ticks - rhs.ticks,
-            ntole_index: self.ntole_index,
+        match self.checked_sub(rhs) {
+            Some(length) => length,
+            None => panic!("The difference of the lengths is too precise to be represented"),
         }
     }
 }
 
 impl std::ops::SubAssign for Length {
     fn sub_assign(&mut self, rhs: Self) {
-        if self.ntole_index != rhs.ntole_index {
-            panic!("Subtracting two different ntoles is not supported.");
-        }
-
-        self.ticks -= rhs.ticks;
+        *self = *self - rhs;
     }
 }
 
@@ -239,16 +276,42 @@ impl std::ops::Mul<u32> for Length {
     type Output = Length;
 
     fn mul(self, rhs: u32) -> Self::Output {
-        Self {
-            ticks: self.ticks * rhs,
-            ntole_index: self.ntole_index,
-        }
+        self.scale(rhs.into(), 1)
     }
 }
 impl std::ops::MulAssign<u32> for Length {
     fn mul_assign(&mut self, rhs: u32) {
-        self.ticks *= rhs;
+        *self = *self * rhs;
     }
 }
 
+impl std::iter::Sum for Length {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(ZERO, |total, length| total + length)
+    }
+}
+
+impl PartialOrd for Length {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Length {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let (a, b, _) = self.common_denominator(*other);
+        a.cmp(&b)
+    }
+}
+
+const fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+
+    if a == 0 { 1 } else { a }
+}
+
 mod tests;
@@ -109,13 +109,74 @@ fn test_count_lengths() {
     let count_2 = Length::count_lengths(&collection_2).unwrap();
     let count_3 = Length::count_lengths(&collection_3).unwrap();
     let count_4 = Length::count_lengths(&collection_4).unwrap();
-    let count_5 = Length::count_lengths(&collection_5);
+    let count_5 = Length::count_lengths(&collection_5).unwrap();
 
     assert_eq!(count_1, WHOLE);
     assert_eq!(count_2, QUARTER * 5);
     assert_eq!(count_3, WHOLE * 2);
     assert_eq!(count_4, WHOLE + HALF);
-    assert!(count_5.is_err());
+    // Incomplete n-toles are counted exactly
+    assert_eq!(count_5, WHOLE * 2 + Length::from_fraction(2, 3));
+}
+
+#[test]
+fn test_mixed_tuplets() {
+    let quintuplet = SIXTEENTH.tuplet(5, 4);
+    let septuplet = EIGTH.tuplet(7, 8);
+
+    assert_eq!(quintuplet * 5, QUARTER);
+    assert_eq!(septuplet * 7, WHOLE);
+
+    // Adding and subtracting across different tuplets
+    assert_eq!(QUARTER.triole() + QUARTER.triole() + quintuplet, HALF.ntole(3) + Length::from_fraction(1, 20));
+    assert_eq!(HALF - QUARTER.triole(), Length::from_fraction(1, 3));
+    assert_eq!(QUARTER.triole() * 3 - HALF, ZERO);
+
+    // Nested tuplets
+    assert_eq!(quintuplet.triole() * 3, quintuplet * 2);
+}
+
+#[test]
+fn test_ordering() {
+    assert!(QUARTER < HALF);
+    assert!(QUARTER.triole() < QUARTER);
+    assert!(EIGTH < QUARTER.triole());
+    assert!(SIXTEENTH.tuplet(5, 4) < SIXTEENTH);
+    assert!(HALF.ntole(5) > EIGTH.dot());
+
+    let mut lengths = vec![HALF, QUARTER.triole(), SIXTEENTH, QUARTER];
+    lengths.sort();
+    assert_eq!(lengths, vec![SIXTEENTH, QUARTER.triole(), QUARTER, HALF]);
+}
+
+#[test]
+fn test_overflow() {
+    // The denominators are coprime, so the sum needs a denominator of about
+    // 2^128
+    let a = Length::from_fraction(1, u64::MAX);
+    let b = Length::from_fraction(1, u64::MAX - 1);
+
+    assert_eq!(a.checked_add(b), None);
+    assert!(Length::count_lengths(&[a, b]).is_err());
+    assert_eq!(a.checked_add(a), Some(Length::from_fraction(2, u64::MAX)));
+
+    // Intermediate values beyond 64 bits are reduced
+    let c = Length::from_fraction(u64::MAX - 1, u64::MAX);
+    assert_eq!(c + a, WHOLE);
+    assert_eq!(WHOLE - a, c);
+    assert_eq!(Length::from_fraction(1, 1 << 40).tuplet(1, 1 << 40), WHOLE);
+}
+
+#[test]
+#[should_panic]
+fn test_overflowing_sum() {
+    let _ = Length::from_fraction(1, u64::MAX) + Length::from_fraction(1, u64::MAX - 1);
+}
+
+#[test]
+#[should_panic]
+fn test_negative_length() {
+    let _ = QUARTER - HALF.triole();
 }
 
 // Utility functions
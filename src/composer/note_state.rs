@@ -0,0 +1,183 @@
+use super::{Note, ScaledValue, Length};
+use super::note::DynamicsFlag;
+use super::note::articulation::{Articulation, LEGATO_PLAY_FRACTION};
+use super::parameter::{Parameter, ParameterMap};
+use super::dynamics::{DynamicsCurve, Dynamic, Accent, DynamicsTable};
+
+/// The settings of a track that apply to the next placed note, shared by
+/// `UnboundTrack` and `MeasureTrack`.
+/// 
+/// Ending a change is split in two: the state is updated once, and every note
+/// that ends the change is updated with `end_dynamics_on()` or
+/// `end_parameter_on()`.
+#[derive(Clone)]
+pub(super) struct NoteState {
+    intensity: f32,
+    play_fraction: f32,
+    parameters: ParameterMap<f32>,
+
+    next_note_dynamic_flag: Option<DynamicsFlag>,
+    next_note_dynamics_curve: DynamicsCurve,
+    next_note_accent: Option<Accent>,
+    next_note_attack_intensity: Option<f32>,
+    slur: Option<SlurState>,
+    next_note_parameter_changes: Vec<Parameter>,
+}
+
+/// The state of a slur on a track.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SlurState {
+    /// No note has been placed since the start of the slur
+    Started,
+    Continued,
+}
+
+impl NoteState {
+    pub fn new() -> Self {
+        Self {
+            intensity: 0.5,
+            play_fraction: 1.0,
+            parameters: ParameterMap::new(),
+            next_note_dynamic_flag: None,
+            next_note_dynamics_curve: DynamicsCurve::Linear,
+            next_note_accent: None,
+            next_note_attack_intensity: None,
+            slur: None,
+            next_note_parameter_changes: Vec::new(),
+        }
+    }
+
+    /// Create the next note from the current settings. `previous` is the
+    /// previous note of the same voice, which is extended by a slur. The
+    /// intensity is multiplied with the `beat_emphasis`.
+    pub fn next_note<T: ScaledValue>(
+        &mut self,
+        length: Length,
+        values: Vec<T>,
+        previous: Option<&mut Note<T>>,
+        beat_emphasis: f32,
+    ) -> Note<T> {
+        let (articulation, slurred) = self.continue_slur(previous);
+
+        let mut parameter_flags = ParameterMap::new();
+        for key in self.next_note_parameter_changes.drain(..) {
+            parameter_flags.set(key, DynamicsFlag::StartChange);
+        }
+
+        Note {
+            values,
+            length,
+            intensity: self.intensity * beat_emphasis,
+            play_fraction: self.play_fraction,
+            articulation,
+            slurred,
+            dynamics_flag: self.next_note_dynamic_flag.take().unwrap_or(DynamicsFlag::None),
+            dynamics_curve: std::mem::take(&mut self.next_note_dynamics_curve),
+            accent: self.next_note_accent.take(),
+            attack_intensity: self.next_note_attack_intensity.take()
+                .map(|attack_intensity| attack_intensity * beat_emphasis),
+            parameters: self.parameters.clone(),
+            parameter_flags,
+            ..Default::default()
+        }
+    }
+
+    /// Update the slur for a new note. The previous note is extended to
+    /// overlap with the new note, and the articulation and `slurred` flag for
+    /// the new note are returned.
+    fn continue_slur<T: ScaledValue>(&mut self, previous: Option<&mut Note<T>>) -> (Option<Articulation>, bool) {
+        let Some(slur) = self.slur else {
+            return (None, false);
+        };
+
+        self.slur = Some(SlurState::Continued);
+
+        if slur == SlurState::Continued {
+            if let Some(previous) = previous {
+                previous.play_fraction = LEGATO_PLAY_FRACTION;
+            }
+        }
+
+        (Some(Articulation::Legato), slur == SlurState::Continued)
+    }
+
+    pub fn start_dynamic_change(&mut self, curve: DynamicsCurve) {
+        self.next_note_dynamic_flag = Some(DynamicsFlag::StartChange);
+        self.next_note_dynamics_curve = curve;
+    }
+
+    pub fn end_dynamic_change(&mut self, intensity: f32) {
+        self.next_note_dynamic_flag = None;
+        self.intensity = intensity;
+    }
+
+    /// End a dynamics change on the last note of a voice. `started` tells
+    /// whether the change was started in the voice of the note.
+    pub fn end_dynamics_on<T: ScaledValue>(note: &mut Note<T>, started: bool, intensity: f32) {
+        match (started, note.dynamics_flag) {
+            // A change over a single note has nothing to interpolate
+            (true, DynamicsFlag::StartChange) => note.dynamics_flag = DynamicsFlag::None,
+            (true, _) => {
+                note.dynamics_flag = DynamicsFlag::EndChange;
+                note.intensity = intensity;
+            }
+            (false, _) => note.intensity = intensity,
+        }
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+
+    pub fn set_dynamic(&mut self, dynamic: Dynamic, dynamics_table: &DynamicsTable) {
+        match dynamic {
+            Dynamic::Sfz => self.next_note_accent = Some(Accent::Sforzando),
+            Dynamic::Fp => {
+                self.next_note_attack_intensity = dynamics_table.intensity(Dynamic::F);
+                self.intensity = dynamics_table.intensity(Dynamic::P).unwrap();
+            }
+            _ => self.intensity = dynamics_table.intensity(dynamic).unwrap(),
+        }
+    }
+
+    pub fn set_play_fraction(&mut self, play_fraction: f32) {
+        self.play_fraction = play_fraction;
+    }
+
+    pub fn set_parameter(&mut self, key: Parameter, value: f32) {
+        self.parameters.set(key, value);
+    }
+
+    pub fn start_parameter_change(&mut self, key: Parameter) {
+        self.next_note_parameter_changes.push(key);
+    }
+
+    pub fn end_parameter_change(&mut self, key: Parameter, value: f32) {
+        self.next_note_parameter_changes.retain(|pending_key| *pending_key != key);
+        self.parameters.set(key, value);
+    }
+
+    /// End a parameter change on the last note of a voice. `started` tells
+    /// whether the change was started in the voice of the note.
+    pub fn end_parameter_on<T: ScaledValue>(note: &mut Note<T>, started: bool, key: Parameter, value: f32) {
+        match (started, note.parameter_flags.get(key)) {
+            // A change over a single note has nothing to interpolate
+            (true, Some(DynamicsFlag::StartChange)) => {
+                note.parameter_flags.remove(key);
+            }
+            (true, _) => {
+                note.parameter_flags.set(key, DynamicsFlag::EndChange);
+                note.parameters.set(key, value);
+            }
+            (false, _) => note.parameters.set(key, value),
+        }
+    }
+
+    pub fn start_slur(&mut self) {
+        self.slur = Some(SlurState::Started);
+    }
+
+    pub fn end_slur(&mut self) {
+        self.slur = None;
+    }
+}
@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+/// A key for an instrument parameter that can be set per note and automated
+/// over a range of notes. Every parameter has a default value, which is used if
+/// the parameter is never set on the track.
+///
+/// Parameters are identified by their name. There are a few common ones
+/// provided, but it's possible to define custom parameters for an instrument:
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// const VIBRATO: Parameter = Parameter::new("vibrato", 0.0);
+/// ```
+///
+/// What a parameter does is up to the instrument. Instruments read the value
+/// with `Tone::parameter()` at render time.
+#[derive(Clone, Copy, Debug)]
+pub struct Parameter {
+    name: &'static str,
+    default: f32,
+}
+
+/// Brightness of the sound, from `0.0` (dull) to `1.0` (bright).
+pub const BRIGHTNESS: Parameter = Parameter::new("brightness", 0.5);
+/// Stereo position, from `-1.0` (left) to `1.0` (right).
+pub const PAN: Parameter = Parameter::new("pan", 0.0);
+/// Cutoff frequency of a filter in Hz.
+pub const CUTOFF: Parameter = Parameter::new("cutoff", 20000.0);
+/// Pitch bend in semitones.
+pub const PITCH_BEND: Parameter = Parameter::new("pitch_bend", 0.0);
+
+/// A small map from `Parameter` keys to values. Notes store the plain values
+/// (`ParameterMap<f32>`), while tones store the value at the start and end of
+/// the tone (`ParameterMap<Range<f32>>`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterMap<V> {
+    entries: Vec<(Parameter, V)>,
+}
+
+impl Parameter {
+    /// Define a new parameter with a unique name and a default value.
+    pub const fn new(name: &'static str, default: f32) -> Self {
+        Self {
+            name,
+            default,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn default_value(&self) -> f32 {
+        self.default
+    }
+}
+
+impl PartialEq for Parameter {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Parameter {}
+
+impl<V> ParameterMap<V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Set the value of a parameter, replacing the previous value.
+    pub fn set(&mut self, key: Parameter, value: V) {
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn get(&self, key: Parameter) -> Option<&V> {
+        self.entries.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: Parameter) -> Option<&mut V> {
+        self.entries.iter_mut()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn remove(&mut self, key: Parameter) -> Option<V> {
+        let index = self.entries.iter().position(|(k, _)| *k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Parameter> + '_ {
+        self.entries.iter().map(|(key, _)| *key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Parameter, &V)> {
+        self.entries.iter().map(|(key, value)| (*key, value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl ParameterMap<Range<f32>> {
+    /// Get the value of a parameter at a position `t` from `0.0` (start) to
+    /// `1.0` (end). Falls back to the parameter's default value.
+    pub fn interpolate(&self, key: Parameter, t: f32) -> f32 {
+        match self.get(key) {
+            Some(range) => range.start + (range.end - range.start) * t,
+            None => key.default_value(),
+        }
+    }
+}
+
+impl<V> Default for ParameterMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+mod tests;
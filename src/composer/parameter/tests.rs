@@ -0,0 +1,44 @@
+#![cfg(test)]
+
+use super::*;
+
+#[test]
+fn set_and_get() {
+    let mut map = ParameterMap::new();
+    assert!(map.is_empty());
+
+    map.set(BRIGHTNESS, 0.2);
+    map.set(PAN, -1.0);
+    map.set(BRIGHTNESS, 0.8);
+
+    assert_eq!(map.get(BRIGHTNESS), Some(&0.8));
+    assert_eq!(map.get(PAN), Some(&-1.0));
+    assert_eq!(map.get(CUTOFF), None);
+    assert_eq!(map.keys().count(), 2);
+
+    assert_eq!(map.remove(PAN), Some(-1.0));
+    assert_eq!(map.get(PAN), None);
+}
+
+#[test]
+fn parameters_are_identified_by_name() {
+    let custom = Parameter::new("brightness", 1.0);
+
+    let mut map = ParameterMap::new();
+    map.set(BRIGHTNESS, 0.3);
+
+    assert_eq!(map.get(custom), Some(&0.3));
+}
+
+#[test]
+fn interpolate_ranges() {
+    let mut map = ParameterMap::new();
+    map.set(CUTOFF, 1000.0..2000.0);
+
+    assert_eq!(map.interpolate(CUTOFF, 0.0), 1000.0);
+    assert_eq!(map.interpolate(CUTOFF, 0.25), 1250.0);
+    assert_eq!(map.interpolate(CUTOFF, 1.0), 2000.0);
+
+    // Unset parameters fall back to their default
+    assert_eq!(map.interpolate(PITCH_BEND, 0.5), PITCH_BEND.default_value());
+}
@@ -0,0 +1,233 @@
+use super::{MusicTrack, ScaledValue, Length, Accent};
+use super::arpeggiator::place_split;
+use super::note::length::ZERO;
+use crate::instrument::Instrument;
+
+/// A grid of steps for programming drums and other repetitive patterns, like a
+/// step sequencer. Every lane places one value (e.g. a drum of a drumset) on
+/// the steps where it is hit.
+/// 
+/// Lanes are written as strings with one character per step:
+/// 
+/// | Character       | Step                                      |
+/// |-----------------|-------------------------------------------|
+/// | `x`             | Hit with full velocity                    |
+/// | `X`             | Accented hit (`Accent::Accent`)           |
+/// | `1` to `9`      | Hit with a velocity of 0.1 to 0.9         |
+/// | `.` or `-`      | Rest                                      |
+/// | ` ` or `\|`     | Ignored, to group the steps for reading   |
+/// 
+/// All lanes hit on a step are placed as stacked notes. A note can only have
+/// one intensity, so stacked hits use the highest velocity.
+/// 
+/// Patterns can be chained and repeated, and the end of a pattern can be
+/// replaced with a fill.
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+/// 
+/// let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+/// 
+/// let (bass, snare, hihat) = (first(2), fifth(3), first(5));
+/// 
+/// let beat = StepPattern::new(SIXTEENTH)
+///     .add_lane(hihat, "x5x5 x5x5 x5x5 x5x5")
+///     .add_lane(snare, ".... X... .... X...")
+///     .add_lane(bass,  "x... ...x x.x. ....");
+/// 
+/// let fill = StepPattern::new(SIXTEENTH.triole())
+///     .add_lane(snare, "x4x4x4xxxXXX");
+/// 
+/// // Three measures of the beat, then a measure ending with a fill
+/// let phrase = beat.clone()
+///     .repeat(3)
+///     .chain(beat.with_fill(&fill));
+/// 
+/// phrase.place(&mut track).unwrap();
+/// track.measure().unwrap();
+/// ```
+/// 
+/// On a `MeasureTrack`, measures that are filled are completed automatically
+/// when more steps follow, and steps that cross a measure boundary are split.
+/// The last measure is left open.
+#[derive(Clone, Debug)]
+pub struct StepPattern<T: ScaledValue> {
+    step_length: Length,
+    steps: Vec<Step<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct Step<T> {
+    length: Length,
+    hits: Vec<Hit<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct Hit<T> {
+    value: T,
+    velocity: f32,
+    accent: bool,
+}
+
+impl<T: ScaledValue> StepPattern<T> {
+    /// Create an empty pattern, where lanes use steps of `step_length`.
+    pub fn new(step_length: Length) -> Self {
+        Self {
+            step_length,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Add a lane written as a string, see `StepPattern` for the format.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the string contains invalid characters.
+    pub fn add_lane(self, value: T, pattern: &str) -> Self {
+        let hits: Vec<Option<(f32, bool)>> = pattern.chars()
+            .filter(|c| !c.is_whitespace() && *c != '|')
+            .map(|c| match c {
+                'x' => Some((1.0, false)),
+                'X' => Some((1.0, true)),
+                '.' | '-' => None,
+                '1'..='9' => Some((c.to_digit(10).unwrap() as f32 / 10.0, false)),
+                _ => panic!("Invalid step '{}' in pattern \"{}\"", c, pattern),
+            })
+            .collect();
+
+        self.add_hits(value, hits)
+    }
+
+    /// Add a lane from a bool for every step, where `true` is a hit.
+    pub fn add_lane_steps(self, value: T, steps: &[bool]) -> Self {
+        let hits = steps.iter()
+            .map(|hit| hit.then_some((1.0, false)))
+            .collect();
+
+        self.add_hits(value, hits)
+    }
+
+    /// Add a lane from a velocity for every step, where a velocity of `0.0`
+    /// is a rest.
+    pub fn add_lane_velocities(self, value: T, velocities: &[f32]) -> Self {
+        let hits = velocities.iter()
+            .map(|velocity| (*velocity > 0.0).then_some((*velocity, false)))
+            .collect();
+
+        self.add_hits(value, hits)
+    }
+
+    /// Append another pattern, which may use a different step length.
+    pub fn chain(mut self, other: Self) -> Self {
+        self.steps.extend(other.steps);
+        self
+    }
+
+    /// Play the pattern `times` times in a row.
+    pub fn repeat(self, times: usize) -> Self {
+        let steps = self.steps.iter()
+            .cycle()
+            .take(self.steps.len() * times)
+            .cloned()
+            .collect();
+
+        Self {
+            steps,
+            ..self
+        }
+    }
+
+    /// Replace the end of the pattern with a fill of the same duration. If
+    /// the fill doesn't line up with the steps, the gap before the fill is
+    /// rested.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the fill is longer than the pattern.
+    pub fn with_fill(mut self, fill: &Self) -> Self {
+        let fill_length = fill.length();
+        if fill_length > self.length() {
+            panic!("The fill is longer than the pattern");
+        }
+
+        let mut removed = ZERO;
+        while removed < fill_length {
+            removed += self.steps.pop().unwrap().length;
+        }
+
+        if removed > fill_length {
+            self.steps.push(Step::rest(removed - fill_length));
+        }
+
+        self.steps.extend(fill.steps.iter().cloned());
+        self
+    }
+
+    /// The number of steps.
+    pub fn num_steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// The total length of all steps.
+    pub fn length(&self) -> Length {
+        self.steps.iter().map(|step| step.length).sum()
+    }
+
+    /// Place the pattern on a track. An error is returned if a measure can't
+    /// be completed.
+    pub fn place<U, M>(&self, track: &mut M) -> Result<(), &'static str>
+    where
+        U: Instrument<ConcreteValue = T::ConcreteValue>,
+        M: MusicTrack<T, U>,
+    {
+        for step in &self.steps {
+            let values = step.hits.iter().map(|hit| hit.value.clone()).collect();
+            let accent = step.hits.iter().any(|hit| hit.accent);
+            let velocity = step.hits.iter()
+                .map(|hit| hit.velocity)
+                .fold(0.0, f32::max);
+
+            place_split(track, step.length, values, |note| {
+                note.intensity *= velocity;
+                if accent {
+                    note.accent = Some(Accent::Accent);
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Merge the hits of a lane into the steps, starting at the first step.
+    fn add_hits(mut self, value: T, hits: Vec<Option<(f32, bool)>>) -> Self {
+        if self.steps.len() < hits.len() {
+            let step_length = self.step_length;
+            self.steps.resize_with(hits.len(), || Step::rest(step_length));
+        }
+
+        for (step, hit) in self.steps.iter_mut().zip(hits) {
+            if let Some((velocity, accent)) = hit {
+                step.hits.push(Hit {
+                    value: value.clone(),
+                    velocity,
+                    accent,
+                });
+            }
+        }
+
+        self
+    }
+}
+
+impl<T> Step<T> {
+    fn rest(length: Length) -> Self {
+        Self {
+            length,
+            hits: Vec::new(),
+        }
+    }
+}
+
+mod tests;
@@ -0,0 +1,171 @@
+#![cfg(test)]
+
+use crate::prelude::*;
+use crate::prelude::length::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Drum {
+    Bass,
+    Snare,
+    HiHat,
+}
+
+impl ScaledValue for Drum {
+    type ConcreteValue = Self;
+
+    fn to_concrete_value(&self, _key: MusicKey) -> Self::ConcreteValue {
+        *self
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Drumset;
+
+impl Instrument for Drumset {
+    type ConcreteValue = Drum;
+
+    fn render_tone_buffer(&self, _tone: Drum, buffer: &mut SoundBuffer, num_samples: usize) {
+        buffer.samples.extend(std::iter::repeat_n(0.0, num_samples));
+    }
+}
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 8000,
+};
+
+const INFO: SectionInfo = SectionInfo {
+    bpm: 120.0,
+    key: music_key::C_MAJOR,
+    settings: &SETTINGS,
+};
+
+fn tones<M: MusicTrack<Drum, Drumset>>(track: &M) -> Vec<Tone<Drum>> {
+    track.convert_to_export_track(INFO).tones
+}
+
+#[test]
+fn lanes_are_stacked() {
+    use Drum::*;
+
+    let pattern = StepPattern::new(EIGTH)
+        .add_lane(HiHat, "xxxx")
+        .add_lane(Snare, ".x.x")
+        .add_lane(Bass,  "x...");
+
+    let mut track = UnboundTrack::new(Drumset);
+    pattern.place(&mut track).unwrap();
+
+    let values: Vec<Vec<Drum>> = tones(&track).into_iter()
+        .map(|tone| tone.concrete_values)
+        .collect();
+
+    assert_eq!(values, vec![
+        vec![HiHat, Bass],
+        vec![HiHat, Snare],
+        vec![HiHat],
+        vec![HiHat, Snare],
+    ]);
+}
+
+#[test]
+fn string_format() {
+    let pattern = StepPattern::new(SIXTEENTH)
+        .add_lane(Drum::Snare, "x.-5 | X...");
+
+    assert_eq!(pattern.num_steps(), 8);
+    assert_eq!(pattern.length(), HALF);
+}
+
+#[test]
+#[should_panic]
+fn invalid_step() {
+    let _ = StepPattern::new(SIXTEENTH).add_lane(Drum::Snare, "x.o.");
+}
+
+#[test]
+fn velocities_and_accents() {
+    let pattern = StepPattern::new(QUARTER)
+        .add_lane(Drum::Snare, "x5X.")
+        .add_lane_velocities(Drum::Bass, &[0.0, 0.8, 0.0]);
+
+    let mut track = UnboundTrack::new(Drumset);
+    track.set_intensity(0.5);
+    pattern.place(&mut track).unwrap();
+
+    let intensities: Vec<f32> = tones(&track).iter()
+        .map(|tone| tone.intensity.start)
+        .collect();
+
+    let accent = DynamicsTable::new().accent_factor(Accent::Accent);
+    let expected = [0.5, 0.4, 0.5 * accent, 0.0];
+
+    assert_eq!(intensities.len(), 4);
+    for (intensity, expected) in intensities.iter().zip(expected).take(3) {
+        assert!((intensity - expected).abs() < 1e-5, "{intensity} != {expected}");
+    }
+}
+
+#[test]
+fn bool_lanes() {
+    let pattern = StepPattern::new(EIGTH)
+        .add_lane_steps(Drum::Bass, &[true, false, false, true]);
+
+    let mut track = UnboundTrack::new(Drumset);
+    pattern.place(&mut track).unwrap();
+
+    let hits: Vec<bool> = tones(&track).iter()
+        .map(|tone| !tone.concrete_values.is_empty())
+        .collect();
+
+    assert_eq!(hits, vec![true, false, false, true]);
+}
+
+#[test]
+fn chain_and_repeat() {
+    let beat = StepPattern::new(QUARTER).add_lane(Drum::Bass, "x.x.");
+    let triplets = StepPattern::new(QUARTER.triole()).add_lane(Drum::Snare, "xxxxxx");
+
+    let phrase = beat.clone().repeat(3).chain(triplets);
+
+    assert_eq!(phrase.num_steps(), 18);
+    assert_eq!(phrase.length(), WHOLE * 4);
+
+    let mut track = MeasureTrack::new(Drumset, TimeSignature::new(4, 4));
+    phrase.place(&mut track).unwrap();
+    beat.with_fill(&StepPattern::new(QUARTER).add_lane(Drum::Snare, "xx"))
+        .place(&mut track)
+        .unwrap();
+    track.measure().unwrap();
+
+    assert_eq!(tones(&track).len(), 18 + 4);
+}
+
+#[test]
+fn fill_replaces_end() {
+    let beat = StepPattern::new(EIGTH).add_lane(Drum::HiHat, "xxxxxxxx");
+    let fill = StepPattern::new(QUARTER.triole()).add_lane(Drum::Snare, "xxx");
+
+    let pattern = beat.with_fill(&fill);
+    assert_eq!(pattern.length(), WHOLE);
+    assert_eq!(pattern.num_steps(), 4 + 3);
+
+    // A fill that doesn't line up with the steps leaves a rest before it
+    let beat = StepPattern::new(QUARTER).add_lane(Drum::HiHat, "xxxx");
+    let fill = StepPattern::new(EIGTH).add_lane(Drum::Snare, "xxx");
+
+    let pattern = beat.with_fill(&fill);
+    assert_eq!(pattern.length(), WHOLE);
+    assert_eq!(pattern.num_steps(), 2 + 1 + 3);
+}
+
+#[test]
+fn steps_are_split_at_measure_boundaries() {
+    let pattern = StepPattern::new(QUARTER.dot()).add_lane(Drum::Bass, "xxxx");
+
+    let mut track = MeasureTrack::new(Drumset, TimeSignature::new(3, 4));
+    pattern.place(&mut track).unwrap();
+    track.measure().unwrap();
+
+    // Tied parts are merged again
+    assert_eq!(tones(&track).len(), 4);
+}
@@ -17,6 +17,7 @@ use super::Length;
 pub struct TimeSignature {
     pub measure_length: Length,
     beat_length: Length,
+    beat_groups: Vec<u8>,
     beat_intensities: Vec<f32>,
     offbeat_intensity: f32,
 }
@@ -25,26 +26,67 @@ impl TimeSignature {
     /// Create a new time signature without specified emphasis. The time
     /// signature is of the form `nominator`/`denominator`.
     /// 
-    /// Currently, only powers of two for the denominator are supported. Other
-    /// values will result in a panic.
+    /// Denominators that aren't a power of two are "irrational" time
+    /// signatures, where a beat is part of an n-tole. E.g. a beat in 4/6 is a
+    /// quarter triole, and a beat in 3/5 is a half quintole.
+    /// 
+    /// ```
+    /// use synth_music::prelude::*;
+    /// use length::*;
+    /// 
+    /// let four_six = TimeSignature::new(4, 6);
+    /// assert_eq!(four_six.beat_length(), QUARTER.triole());
+    /// ```
+    /// 
+    /// Panics if the nominator or denominator is zero.
     pub fn new(nominator: u8, denominator: u8) -> Self {
-        if ! denominator.is_power_of_two() {
-            panic!("The denominator can only be a power of two.");
-        }
         if nominator < 1 {
             panic!("The nominator needs to be a positive integer.");
         }
 
-        let subdivision = Self::what_power_of_two(denominator);
+        Self::additive(&vec![1; nominator.into()], denominator)
+    }
+
+    /// Create an additive time signature, where beats are made of groups of
+    /// different sizes, e.g. (3+2+2)/8. Every group is a beat that can be
+    /// emphasized with `set_beat()`; the first group is beat `0`.
+    /// 
+    /// ```
+    /// use synth_music::prelude::*;
+    /// use tet12::*;
+    /// use length::*;
+    /// 
+    /// // (3+2+2)/8, with an emphasis on the start of every group
+    /// let seven_eight = TimeSignature::additive(&[3, 2, 2], 8)
+    ///     .set_beat(0, 1.2)
+    ///     .set_beat(1, 1.1)
+    ///     .set_beat(2, 1.1)
+    ///     .set_offbeat(0.9);
+    /// 
+    /// let mut track = MeasureTrack::new(predefined::SineGenerator, seven_eight);
+    /// track.note(QUARTER.dot(), first(4));
+    /// track.note(QUARTER, third(4));
+    /// track.note(QUARTER, fifth(4));
+    /// track.measure().unwrap();
+    /// ```
+    /// 
+    /// Panics if a group or the denominator is zero, or if there are no
+    /// groups.
+    pub fn additive(groups: &[u8], denominator: u8) -> Self {
+        if groups.is_empty() || groups.contains(&0) {
+            panic!("The groups need to be positive integers.");
+        }
 
-        let beat_length = Length::from_subdivisions(subdivision.into());
-        let measure_length = beat_length * nominator.into();
-        let beat_intensities = vec![1.0; nominator.into()];
+        let beat_length = Self::unit_length(denominator);
+        let units: u32 = groups.iter().map(|group| *group as u32).sum();
+        let measure_length = beat_length * units;
+        let beat_intensities = vec![1.0; groups.len()];
         let offbeat_intensity = 1.0;
 
         Self {
             measure_length,
             beat_length,
+            beat_groups: groups.to_vec(),
             beat_intensities,
             offbeat_intensity,
         }
@@ -75,32 +117,58 @@ impl TimeSignature {
         return self.measure_length == lengths;
     }
 
+    /// Checks if the given note lengths together fit perfectly inside the
+    /// measure.
+    pub fn is_measure_filled(&self, lengths: &[Length]) -> bool {
+        self.measure_length == lengths.iter().copied().sum()
+    }
+
+    /// Get the emphasis of a note that starts after the given note lengths
+    /// in the measure.
+    pub fn emphasis_at(&self, preceding: &[Length]) -> f32 {
+        let position: Length = preceding.iter().copied().sum();
+        let mut units = 0;
+
+        for (group, intensity) in self.beat_groups.iter().zip(&self.beat_intensities) {
+            if position == self.beat_length * units {
+                return *intensity;
+            }
+
+            units += *group as u32;
+        }
+
+        self.offbeat_intensity
+    }
+
     /// Return a reference to the beat emphasis values.
     pub fn beats(&self) -> &Vec<f32> {
         &self.beat_intensities
     }
 
-    /// Return the length of a single beat.
+    /// Return the length of a single beat. In additive time signatures, this
+    /// is the length of a single unit of a group.
     pub fn beat_length(&self) -> Length {
         self.beat_length
     }
 
+    /// Return the number of units in every beat. This is `1` for every beat in
+    /// time signatures that aren't additive.
+    pub fn beat_groups(&self) -> &[u8] {
+        &self.beat_groups
+    }
+
     /// Return the intensity of an offbeat.
     pub fn offbeat_intensity(&self) -> f32 {
         self.offbeat_intensity
     }
 
-    fn what_power_of_two(mut value: u8) -> u8 {
-        let mut result = 0;
-
-        if value == 0  { panic!("Invalid input") };
-
-        while value > 1 {
-            value >>= 1;
-            result += 1;
+    /// The length of a `1/denominator` note.
+    fn unit_length(denominator: u8) -> Length {
+        if denominator == 0 {
+            panic!("The denominator needs to be a positive integer.");
         }
 
-        return result;
+        Length::from_fraction(1, denominator.into())
     }
 }
 
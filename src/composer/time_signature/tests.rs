@@ -15,6 +15,7 @@ fn test_construction() {
     assert_eq!(four_four, TimeSignature {
         measure_length: WHOLE,
         beat_length: QUARTER,
+        beat_groups: vec![1; 4],
         beat_intensities: vec![1.0; 4],
         offbeat_intensity: 1.0,
     });
@@ -22,6 +23,7 @@ fn test_construction() {
     assert_eq!(one_four, TimeSignature {
         measure_length: QUARTER,
         beat_length: QUARTER,
+        beat_groups: vec![1; 1],
         beat_intensities: vec![1.0; 1],
         offbeat_intensity: 1.0,
     });
@@ -29,6 +31,7 @@ fn test_construction() {
     assert_eq!(three_eight, TimeSignature {
         measure_length: EIGTH * 3,
         beat_length: EIGTH,
+        beat_groups: vec![1; 3],
         beat_intensities: vec![1.0; 3],
         offbeat_intensity: 1.0,
     });
@@ -36,6 +39,7 @@ fn test_construction() {
     assert_eq!(six_eight, TimeSignature {
         measure_length: EIGTH * 6,
         beat_length: EIGTH,
+        beat_groups: vec![1; 6],
         beat_intensities: vec![1.0; 6],
         offbeat_intensity: 1.0,
     });
@@ -43,6 +47,7 @@ fn test_construction() {
     assert_eq!(five_four, TimeSignature {
         measure_length: WHOLE + QUARTER,
         beat_length: QUARTER,
+        beat_groups: vec![1; 5],
         beat_intensities: vec![1.0; 5],
         offbeat_intensity: 1.0,
     });
@@ -50,6 +55,7 @@ fn test_construction() {
     assert_eq!(thirtytwo_one, TimeSignature {
         measure_length: WHOLE * 32,
         beat_length: WHOLE,
+        beat_groups: vec![1; 32],
         beat_intensities: vec![1.0; 32],
         offbeat_intensity: 1.0,
     });
@@ -58,7 +64,7 @@ fn test_construction() {
 #[test]
 #[should_panic]
 fn test_wrong_construction_1() {
-    TimeSignature::new(4, 5);
+    TimeSignature::additive(&[3, 0, 2], 8);
 }
 
 #[test]
@@ -93,3 +99,52 @@ fn test_measure_saturation() {
     assert!(! four_four.is_measure_saturated(WHOLE + SIXTEENTH));
     assert!(! six_eight.is_measure_saturated(QUARTER * 3 - EIGTH));
 }
+
+#[test]
+fn test_irrational_construction() {
+    let four_six = TimeSignature::new(4, 6);
+    let three_five = TimeSignature::new(3, 5);
+    let two_twelve = TimeSignature::new(2, 12);
+
+    assert_eq!(four_six.beat_length(), QUARTER.triole());
+    assert_eq!(three_five.beat_length(), HALF.ntole(5));
+    assert_eq!(two_twelve.beat_length(), EIGTH.triole());
+
+    // Two thirds of a whole note
+    assert!(four_six.is_measure_filled(&[QUARTER.triole(); 4]));
+    assert!(four_six.is_measure_filled(&[HALF, QUARTER.triole()]));
+    assert!(! four_six.is_measure_filled(&[HALF, QUARTER]));
+    assert!(three_five.is_measure_filled(&[HALF.ntole(5); 3]));
+}
+
+#[test]
+fn test_additive() {
+    let seven_eight = TimeSignature::additive(&[3, 2, 2], 8)
+        .set_beat(0, 1.3)
+        .set_beat(1, 1.2)
+        .set_beat(2, 1.1)
+        .set_offbeat(0.5);
+
+    assert_eq!(seven_eight.measure_length, EIGTH * 7);
+    assert_eq!(seven_eight.beat_groups(), &[3, 2, 2]);
+
+    assert_eq!(seven_eight.emphasis_at(&[]), 1.3);
+    assert_eq!(seven_eight.emphasis_at(&[EIGTH]), 0.5);
+    assert_eq!(seven_eight.emphasis_at(&[EIGTH, QUARTER]), 1.2);
+    assert_eq!(seven_eight.emphasis_at(&[QUARTER.dot(), EIGTH]), 0.5);
+    assert_eq!(seven_eight.emphasis_at(&[QUARTER.dot(), QUARTER]), 1.1);
+
+    assert!(seven_eight.is_measure_filled(&[QUARTER.dot(), QUARTER, QUARTER]));
+}
+
+#[test]
+fn test_emphasis_irrational() {
+    let four_six = TimeSignature::new(4, 6)
+        .set_beat(2, 1.5)
+        .set_offbeat(0.5);
+
+    let sixth = QUARTER.triole();
+
+    assert_eq!(four_six.emphasis_at(&[sixth, sixth]), 1.5);
+    assert_eq!(four_six.emphasis_at(&[QUARTER]), 0.5);
+}
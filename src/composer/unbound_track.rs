@@ -1,5 +1,10 @@
 use super::{Note, ScaledValue, Length};
 use super::note::DynamicsFlag;
+use super::note_state::NoteState;
+use super::parameter::{Parameter, ParameterMap};
+use super::dynamics::{DynamicsCurve, DynamicsChange, Dynamic, DynamicsTable, FORTEPIANO_DROP};
+use super::groove::Groove;
+use super::humanize::Humanize;
 
 use super::{SectionInfo, MusicTrack};
 use super::{ExportTrack, Tone};
@@ -22,11 +27,12 @@ use std::time::Duration;
 pub struct UnboundTrack<T: ScaledValue, U: Instrument> {
     notes: Vec<Note<T>>,
     instrument: U,
+    instrument_changes: Vec<(usize, U)>,
 
-    current_intensity: f32,
-    current_play_fraction: f32,
-
-    next_note_dynamic_flag: Option<DynamicsFlag>,
+    note_state: NoteState,
+    dynamics_table: DynamicsTable,
+    groove: Option<Groove>,
+    humanize: Option<Humanize>,
 }
 
 impl<T, U> MusicTrack<T, U> for UnboundTrack<T, U>
@@ -43,42 +49,67 @@ where
     }
 
     fn notes(&mut self, length: Length, values: Vec<T>) -> &mut Note<T> {
-        let intensity = self.current_intensity;
-        let play_fraction = self.current_play_fraction;
-
-        let dynamics_flag = self.next_note_dynamic_flag.take().unwrap_or(DynamicsFlag::None);
-
-        self.notes.push(Note {
-            values,
-            length,
-            intensity,
-            play_fraction,
-            dynamics_flag,
-            ..Default::default()
-        });
+        let note = self.note_state.next_note(length, values, self.notes.last_mut(), 1.0);
+        self.notes.push(note);
 
         let last_index = self.notes.len() - 1;
         return &mut self.notes[last_index];
     }
 
     fn start_dynamic_change(&mut self) {
-        self.next_note_dynamic_flag = Some(DynamicsFlag::StartChange);
+        self.start_dynamic_change_with(DynamicsCurve::Linear);
+    }
+
+    fn start_dynamic_change_with(&mut self, curve: DynamicsCurve) {
+        self.note_state.start_dynamic_change(curve);
     }
 
     fn end_dynamic_change(&mut self, intensity: f32) {
-        let active_note = self.notes.last_mut().unwrap();
+        self.note_state.end_dynamic_change(intensity);
 
-        active_note.dynamics_flag = DynamicsFlag::EndChange;
-        active_note.intensity = intensity;
-        self.current_intensity = intensity;
+        if let Some(active_note) = self.notes.last_mut() {
+            NoteState::end_dynamics_on(active_note, true, intensity);
+        }
     }
 
     fn set_intensity(&mut self, intensity: f32) {
-        self.current_intensity = intensity;
+        self.note_state.set_intensity(intensity);
+    }
+
+    fn set_dynamic(&mut self, dynamic: Dynamic) {
+        self.note_state.set_dynamic(dynamic, &self.dynamics_table);
     }
 
     fn set_play_fraction(&mut self, play_fraction: f32) {
-        self.current_play_fraction = play_fraction;
+        self.note_state.set_play_fraction(play_fraction);
+    }
+
+    fn set_parameter(&mut self, key: Parameter, value: f32) {
+        self.note_state.set_parameter(key, value);
+    }
+
+    fn start_parameter_change(&mut self, key: Parameter) {
+        self.note_state.start_parameter_change(key);
+    }
+
+    fn end_parameter_change(&mut self, key: Parameter, value: f32) {
+        self.note_state.end_parameter_change(key, value);
+
+        if let Some(active_note) = self.notes.last_mut() {
+            NoteState::end_parameter_on(active_note, true, key, value);
+        }
+    }
+
+    fn start_slur(&mut self) {
+        self.note_state.start_slur();
+    }
+
+    fn end_slur(&mut self) {
+        self.note_state.end_slur();
+    }
+
+    fn set_instrument(&mut self, instrument: U) {
+        self.set_instrument_at(self.notes.len(), instrument);
     }
 
     fn get_active_note(&mut self) -> Option<&mut Note<T>> {
@@ -88,11 +119,26 @@ where
     fn convert_to_export_track(&self, section_info: SectionInfo) -> ExportTrack<U> {
         let mut tones = self.conversion_first_pass(section_info);
 
-        self.conversion_pass_dynamics(&mut tones);
+        let dynamics_changes = self.conversion_pass_dynamics(&mut tones);
+        self.conversion_pass_parameters(&mut tones);
+        self.conversion_pass_accents(&mut tones);
+
+        let (mut tones, tone_indices) = self.conversion_pass_ties(tones, &dynamics_changes);
+        let instrument_changes = self.remap_instrument_changes(&tone_indices, tones.len());
+
+        if let Some(groove) = &self.groove {
+            groove.apply(&mut tones, section_info.bpm);
+        }
+
+        if let Some(humanize) = &self.humanize {
+            humanize.apply(&mut tones);
+        }
 
         ExportTrack {
             tones,
             instrument: self.instrument.clone(),
+            instrument_changes,
+            time_signatures: Vec::new(),
         }
     }
 }
@@ -107,9 +153,11 @@ where
         Self {
             notes: Vec::new(),
             instrument,
-            current_intensity: 0.5,
-            current_play_fraction: 1.0,
-            next_note_dynamic_flag: None,
+            instrument_changes: Vec::new(),
+            note_state: NoteState::new(),
+            dynamics_table: DynamicsTable::new(),
+            groove: None,
+            humanize: None,
         }
     }
 
@@ -119,11 +167,73 @@ where
         }
     }
 
+    /// Replace the table used for looking up `Dynamic` markings and accents.
+    pub fn set_dynamics_table(&mut self, dynamics_table: DynamicsTable) {
+        self.dynamics_table = dynamics_table;
+    }
+
+    /// Apply a swing or groove to the track when converting it. The notes
+    /// are placed straight, see `Groove` for details.
+    pub fn set_groove(&mut self, groove: Groove) {
+        self.groove = Some(groove);
+    }
+
+    /// Apply random variations to the track when converting it, see
+    /// `Humanize` for details.
+    pub fn set_humanize(&mut self, humanize: Humanize) {
+        self.humanize = Some(humanize);
+    }
+
+    pub(super) fn dynamics_table(&self) -> &DynamicsTable {
+        &self.dynamics_table
+    }
+
+    /// A track with the same instrument and settings, but without notes.
+    pub(super) fn empty_copy(&self) -> Self {
+        let mut track = Self::new(self.instrument.clone());
+        track.dynamics_table = self.dynamics_table;
+        track
+    }
+
+    pub(super) fn note_lengths(&self) -> Vec<Length> {
+        self.notes.iter().map(|note| note.length).collect()
+    }
+
+    pub(super) fn num_notes(&self) -> usize {
+        self.notes.len()
+    }
+
+    /// Change the instrument starting from the note at `index`. Used by
+    /// tracks wrapping an `UnboundTrack` that don't append their notes
+    /// immediately.
+    pub(super) fn set_instrument_at(&mut self, index: usize, instrument: U) {
+        if index == 0 {
+            self.instrument = instrument;
+            return;
+        }
+
+        // A later change at the same position replaces the earlier one
+        if let Some(last_change) = self.instrument_changes.last_mut() {
+            if last_change.0 == index {
+                last_change.1 = instrument;
+                return;
+            }
+        }
+
+        self.instrument_changes.push((index, instrument));
+    }
+
     fn conversion_first_pass(&self, section_info: SectionInfo) -> Vec<Tone<U::ConcreteValue>> {
         let mut tones = Vec::new();
 
         for note in &self.notes {
-            let tone = Self::generate_tone(note, section_info);
+            let mut tone = Self::generate_tone(note, section_info);
+
+            if note.slurred {
+                let previous: Option<&Tone<U::ConcreteValue>> = tones.last();
+                tone.slurred_from = previous.map(|previous| previous.concrete_values.clone());
+            }
+
             tones.push(tone);
         }
 
@@ -132,29 +242,173 @@ where
 
     // WARNING: Assumes that notes align with tones
     // Fix if this doesn't apply anymore
-    fn conversion_pass_dynamics(&self, tones: &mut Vec<Tone<U::ConcreteValue>>) {
+    // Returns the index of the dynamics change every note belongs to.
+    fn conversion_pass_dynamics(&self, tones: &mut Vec<Tone<U::ConcreteValue>>) -> Vec<Option<usize>> {
+        let mut changes = vec![None; self.notes.len()];
         let mut i = 0;
+        let mut change = 0;
 
-        while let Some(notes_range) = Self::find_next_dynamics_change(&self.notes, i) {
+        while let Some(notes_range) = Self::find_next_change(&self.notes, i, |note| note.dynamics_flag) {
             i = notes_range.end;
-            Self::calculate_dynamics_over_notes(tones, notes_range);
+            changes[notes_range.clone()].fill(Some(change));
+            change += 1;
+
+            let curve = &self.notes[notes_range.start].dynamics_curve;
+            Self::calculate_dynamics_over_notes(tones, curve, notes_range);
+        }
+
+        changes
+    }
+
+    // WARNING: Assumes that notes align with tones
+    // Fix if this doesn't apply anymore
+    fn conversion_pass_parameters(&self, tones: &mut [Tone<U::ConcreteValue>]) {
+        let mut keys: Vec<Parameter> = Vec::new();
+
+        for note in &self.notes {
+            for key in note.parameter_flags.keys() {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        for key in keys {
+            let mut i = 0;
+            let flag = |note: &Note<T>| {
+                note.parameter_flags.get(key).copied().unwrap_or(DynamicsFlag::None)
+            };
+
+            while let Some(notes_range) = Self::find_next_change(&self.notes, i, flag) {
+                i = notes_range.end;
+                Self::calculate_parameter_over_notes(tones, key, notes_range);
+            }
+        }
+    }
+
+    // WARNING: Assumes that notes align with tones
+    // Fix if this doesn't apply anymore
+    fn conversion_pass_accents(&self, tones: &mut [Tone<U::ConcreteValue>]) {
+        for (note, tone) in self.notes.iter().zip(tones) {
+            let Some(accent) = note.accent else {
+                continue;
+            };
+
+            let factor = self.dynamics_table.accent_factor(accent);
+
+            tone.intensity = tone.intensity.start * factor..tone.intensity.end * factor;
+            if let Some(dynamics) = &mut tone.dynamics {
+                dynamics.scale(factor);
+            }
         }
     }
 
-    fn find_next_dynamics_change(notes: &Vec<Note<T>>, start_index: usize) -> Option<Range<usize>> {
+    // Notes don't align with tones after this pass, so this must be the last
+    // pass. Returns the merged tones, and the index of the tone for every note.
+    // A tie into a pause or into other values is ignored.
+    fn conversion_pass_ties(
+        &self,
+        tones: Vec<Tone<U::ConcreteValue>>,
+        dynamics_changes: &[Option<usize>],
+    ) -> (Vec<Tone<U::ConcreteValue>>, Vec<usize>) {
+        let mut merged_tones: Vec<Tone<U::ConcreteValue>> = Vec::with_capacity(tones.len());
+        let mut tone_indices = Vec::with_capacity(self.notes.len());
+        let mut tied_to_previous = false;
+
+        for (i, (note, tone)) in self.notes.iter().zip(tones).enumerate() {
+            match merged_tones.last_mut() {
+                Some(previous) if tied_to_previous && Self::can_tie(previous, &tone) => {
+                    let same_change = dynamics_changes[i].is_some() && dynamics_changes[i] == dynamics_changes[i - 1];
+                    Self::merge_tied_tone(previous, tone, same_change);
+                }
+                _ => merged_tones.push(tone),
+            }
+
+            tone_indices.push(merged_tones.len() - 1);
+            tied_to_previous = note.tied;
+        }
+
+        (merged_tones, tone_indices)
+    }
+
+    fn can_tie(tone: &Tone<U::ConcreteValue>, next: &Tone<U::ConcreteValue>) -> bool {
+        !next.concrete_values.is_empty() && tone.concrete_values == next.concrete_values
+    }
+
+    /// Merge the next tone into a tied tone. `same_change` tells whether both
+    /// tones belong to the same dynamics change.
+    fn merge_tied_tone(tone: &mut Tone<U::ConcreteValue>, next: Tone<U::ConcreteValue>, same_change: bool) {
+        let first_duration = tone.play_duration;
+
+        tone.tone_duration = tone.play_duration + next.tone_duration;
+        tone.play_duration += next.play_duration;
+        tone.intensity = tone.intensity.start..next.intensity.end;
+
+        // Both parts belong to the same dynamics change, otherwise the
+        // intensity is interpolated linearly over the merged tone
+        tone.dynamics = match (tone.dynamics.take(), next.dynamics) {
+            (Some(mut dynamics), Some(next_dynamics)) if same_change => {
+                dynamics.position = dynamics.position.start..next_dynamics.position.end;
+                Some(dynamics)
+            }
+            // A change that ends within the first part (fortepiano) is kept,
+            // and the intensity is held over the next part
+            (Some(mut dynamics), None) if dynamics.position.end >= 1.0 && !first_duration.is_zero() => {
+                let scale = tone.play_duration.div_duration_f32(first_duration);
+                let position = &dynamics.position;
+                dynamics.position = position.start..position.start + (position.end - position.start) * scale;
+                Some(dynamics)
+            }
+            _ => None,
+        };
+
+        for (key, value) in next.parameters.iter() {
+            match tone.parameters.get_mut(key) {
+                Some(previous) => previous.end = value.end,
+                None => tone.parameters.set(key, key.default_value()..value.end),
+            }
+        }
+    }
+
+    /// Instrument changes are placed at note indices, which need to be
+    /// converted into tone indices after merging tied notes. A change in the
+    /// middle of tied notes applies to the tone after.
+    fn remap_instrument_changes(&self, tone_indices: &[usize], num_tones: usize) -> Vec<(usize, U)> {
+        let mut instrument_changes: Vec<(usize, U)> = Vec::new();
+
+        for (note_index, instrument) in &self.instrument_changes {
+            let tone_index = match tone_indices.get(*note_index) {
+                Some(tone_index) if self.notes[*note_index - 1].tied => tone_index + 1,
+                Some(tone_index) => *tone_index,
+                None => num_tones,
+            };
+
+            match instrument_changes.last_mut() {
+                Some(last_change) if last_change.0 == tone_index => last_change.1 = instrument.clone(),
+                _ => instrument_changes.push((tone_index, instrument.clone())),
+            }
+        }
+
+        instrument_changes
+    }
+
+    fn find_next_change<F>(notes: &Vec<Note<T>>, start_index: usize, flag: F) -> Option<Range<usize>>
+    where
+        F: Fn(&Note<T>) -> DynamicsFlag,
+    {
         let mut index_dynamics_start = None;
 
         for i in start_index..notes.len() {
             let note = &notes[i];
 
-            if note.dynamics_flag == DynamicsFlag::StartChange {
+            if flag(note) == DynamicsFlag::StartChange {
                 if index_dynamics_start.is_some() {
                     panic!("Doubled StartChange.");
                 }
                 
                 index_dynamics_start = Some(i);
             }
-            if note.dynamics_flag == DynamicsFlag::EndChange {
+            if flag(note) == DynamicsFlag::EndChange {
                 if let Some(index_dynamics_start) = index_dynamics_start {
                     return Some(index_dynamics_start..(i + 1));
                 }
@@ -170,35 +424,74 @@ where
         return None;
     }
 
-    fn calculate_dynamics_over_notes(tones: &mut Vec<Tone<U::ConcreteValue>>, range: Range<usize>) {
+    fn calculate_dynamics_over_notes(
+        tones: &mut Vec<Tone<U::ConcreteValue>>,
+        curve: &DynamicsCurve,
+        range: Range<usize>,
+    ) {
         let start_intensity = tones[range.start].intensity.start;
         let end_intensity = tones[range.end - 1].intensity.start;
 
+        let positions = Self::relative_positions(&tones[range.clone()]);
+
+        for (tone, t) in tones[range].iter_mut().zip(positions) {
+            let intensity_at_start = curve.interpolate(start_intensity, end_intensity, t.start);
+            let intensity_at_end = curve.interpolate(start_intensity, end_intensity, t.end);
+
+            tone.intensity = intensity_at_start..intensity_at_end;
+            tone.dynamics = Some(DynamicsChange {
+                curve: curve.clone(),
+                intensity: start_intensity..end_intensity,
+                position: t,
+            });
+        }
+    }
+
+    fn calculate_parameter_over_notes(tones: &mut [Tone<U::ConcreteValue>], key: Parameter, range: Range<usize>) {
+        let value_of = |tone: &Tone<U::ConcreteValue>| {
+            tone.parameters.get(key)
+                .map(|value| value.start)
+                .unwrap_or(key.default_value())
+        };
+
+        let start_value = value_of(&tones[range.start]);
+        let end_value = value_of(&tones[range.end - 1]);
+
+        let positions = Self::relative_positions(&tones[range.clone()]);
+
+        for (tone, t) in tones[range].iter_mut().zip(positions) {
+            let value_at_start = Self::interpolate_linear(start_value, end_value, t.start);
+            let value_at_end = Self::interpolate_linear(start_value, end_value, t.end);
+
+            tone.parameters.set(key, value_at_start..value_at_end);
+        }
+    }
+
+    /// The start and end of every tone relative to the total duration of all
+    /// given tones, in the range from `0.0` to `1.0`.
+    fn relative_positions(tones: &[Tone<U::ConcreteValue>]) -> Vec<Range<f32>> {
         let mut time_delta = Duration::ZERO;
 
-        for tone in &tones[range.clone()] {
+        for tone in tones {
             time_delta += tone.play_duration;
         }
 
-        // We'll assume linear intensity increase
-        // TODO: Make interpolation function custom
-
         let time_delta = time_delta; // Make immutable
         let mut current_time = Duration::ZERO;
+        let mut positions = Vec::with_capacity(tones.len());
 
-        for tone in &mut tones[range] {
-            let t = current_time.as_secs_f32() / time_delta.as_secs_f32();
-            let intensity_at_start = Self::interpolate_intensity(start_intensity, end_intensity, t);
-            
+        for tone in tones {
+            let start = current_time.as_secs_f32() / time_delta.as_secs_f32();
             current_time += tone.play_duration;
-            let t = current_time.as_secs_f32() / time_delta.as_secs_f32();
-            let intensity_at_end = Self::interpolate_intensity(start_intensity, end_intensity, t);
+            let end = current_time.as_secs_f32() / time_delta.as_secs_f32();
 
-            tone.intensity = intensity_at_start..intensity_at_end;
+            positions.push(start..end);
         }
+
+        positions
     }
 
-    fn interpolate_intensity(a: f32, b: f32, t: f32) -> f32 {
+    fn interpolate_linear(a: f32, b: f32, t: f32) -> f32 {
         a + (b - a) * t
     }
 
@@ -213,11 +506,34 @@ where
         let play_duration = note.get_duration(section_info.bpm);
         let tone_duration = play_duration.mul_f32(note.play_fraction);
 
+        let mut parameters = ParameterMap::new();
+        for (key, value) in note.parameters.iter() {
+            parameters.set(key, *value..*value);
+        }
+
+        // Fortepiano falls from the attack intensity to the note intensity at
+        // the start of the tone, and then holds the note intensity
+        let (intensity, dynamics) = match note.attack_intensity {
+            Some(attack_intensity) => (
+                attack_intensity..note.intensity,
+                Some(DynamicsChange {
+                    curve: DynamicsCurve::Exponential,
+                    intensity: attack_intensity..note.intensity,
+                    position: 0.0..play_duration.div_duration_f32(FORTEPIANO_DROP),
+                }),
+            ),
+            None => (note.intensity..note.intensity, None),
+        };
+
         Tone {
             concrete_values,
             play_duration,
             tone_duration,
-            intensity: note.intensity..note.intensity,
+            intensity,
+            dynamics,
+            articulation: note.articulation,
+            parameters,
+            ..Default::default()
         }
     }
 }
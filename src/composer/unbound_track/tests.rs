@@ -63,12 +63,14 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 0.25),
             tone_duration: Duration::from_secs_f32(time_whole * 0.25),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(D4)],
             play_duration: Duration::from_secs_f32(time_whole * 0.5),
             tone_duration: Duration::from_secs_f32(time_whole * 0.5),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
 
         Tone {
@@ -76,24 +78,28 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 0.125),
             tone_duration: Duration::from_secs_f32(time_whole * 0.125),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(F4 - 12)],
             play_duration: Duration::from_secs_f32(time_whole * 0.125),
             tone_duration: Duration::from_secs_f32(time_whole * 0.125),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(G4 - 12 + 1)],
             play_duration: Duration::from_secs_f32(time_whole * 0.125),
             tone_duration: Duration::from_secs_f32(time_whole * 0.125),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(A4 - 12)],
             play_duration: Duration::from_secs_f32(time_whole * 0.125),
             tone_duration: Duration::from_secs_f32(time_whole * 0.125),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
 
         Tone {
@@ -106,6 +112,7 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 1.0),
             tone_duration: Duration::from_secs_f32(time_whole * 1.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
 
         Tone {
@@ -113,18 +120,21 @@ fn conversion_simple() {
             play_duration: Duration::from_secs_f32(time_whole * 0.5 / 3.0),
             tone_duration: Duration::from_secs_f32(time_whole * 0.5 / 3.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(E4)],
             play_duration: Duration::from_secs_f32(time_whole * 0.5 / 3.0),
             tone_duration: Duration::from_secs_f32(time_whole * 0.5 / 3.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
         Tone {
             concrete_values: vec![TET12ConcreteTone(G4)],
             play_duration: Duration::from_secs_f32(time_whole * 0.5 / 3.0),
             tone_duration: Duration::from_secs_f32(time_whole * 0.5 / 3.0),
             intensity: 1.0..1.0,
+            ..Default::default()
         },
     ];
 
@@ -244,6 +254,394 @@ fn conversion_dynamics() {
     }
 }
 
+#[test]
+fn conversion_dynamics_curve() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_intensity(0.0625);
+
+    track.start_dynamic_change_with(DynamicsCurve::Logarithmic);
+    sequential_notes!(track, QUARTER, first(4), first(4), first(4));
+    track.note(QUARTER, first(4));
+    track.end_dynamic_change(1.0);
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+    let expected = [0.0625, 0.125, 0.25, 0.5];
+
+    for (tone, expected) in result.iter().zip(expected) {
+        assert_eq_f32(tone.intensity.start, expected, 0.0001);
+        assert_eq_f32(tone.intensity.end, expected * 2.0, 0.0001);
+    }
+
+    // The shape is also followed within a tone
+    let half = result[0].play_duration / 2;
+    assert_eq_f32(result[0].intensity_at(half), 0.0625 * 2_f32.sqrt(), 0.0001);
+}
+
+#[test]
+fn conversion_articulations() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_intensity(1.0);
+
+    track.note(QUARTER, first(4)).staccato();
+    track.note(QUARTER, first(4)).staccatissimo();
+    track.note(QUARTER, first(4)).portato();
+    track.note(QUARTER, first(4)).tenuto();
+    track.note(QUARTER, first(4)).marcato();
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let table = DynamicsTable::new();
+    let result = track.convert_to_export_track(info).tones;
+
+    let expected = [
+        (Articulation::Staccato, 1.0),
+        (Articulation::Staccatissimo, table.accent_factor(Accent::Accent)),
+        (Articulation::Portato, 1.0),
+        (Articulation::Tenuto, 1.0),
+        (Articulation::Marcato, table.accent_factor(Accent::Marcato)),
+    ];
+
+    for (tone, (articulation, intensity)) in result.iter().zip(expected) {
+        let play_fraction = tone.tone_duration.as_secs_f32() / tone.play_duration.as_secs_f32();
+
+        assert_eq!(tone.articulation, Some(articulation));
+        assert_eq_f32(play_fraction, articulation.play_fraction(), 0.001);
+        assert_eq_f32(tone.intensity.start, intensity, 0.0001);
+    }
+}
+
+#[test]
+fn conversion_slur() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_play_fraction(0.9);
+
+    track.note(QUARTER, first(4));
+    track.start_slur();
+    sequential_notes!(track, QUARTER, second(4), third(4), fourth(4));
+    track.end_slur();
+    track.note(QUARTER, fifth(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    let play_fractions: Vec<f32> = result.iter()
+        .map(|tone| tone.tone_duration.as_secs_f32() / tone.play_duration.as_secs_f32())
+        .collect();
+    let expected = [0.9, 1.1, 1.1, 0.9, 0.9];
+
+    for (play_fraction, expected) in play_fractions.into_iter().zip(expected) {
+        assert_eq_f32(play_fraction, expected, 0.001);
+    }
+
+    let articulations: Vec<_> = result.iter().map(|tone| tone.articulation).collect();
+    assert_eq!(articulations, vec![
+        None,
+        Some(Articulation::Legato),
+        Some(Articulation::Legato),
+        Some(Articulation::Legato),
+        None,
+    ]);
+
+    let slurred_from: Vec<_> = result.iter().map(|tone| tone.slurred_from.clone()).collect();
+    assert_eq!(slurred_from, vec![
+        None,
+        None,
+        Some(vec![TET12ConcreteTone(D4)]),
+        Some(vec![TET12ConcreteTone(E4)]),
+        None,
+    ]);
+}
+
+#[test]
+fn conversion_ties() {
+    let mut track = UnboundTrack::new(Tagged(0));
+    track.set_play_fraction(0.5);
+
+    track.note(QUARTER, first(4)).tie();
+    track.note(SIXTEENTH, first(4)).tie();
+    // Changing in the middle of tied notes applies after the tie
+    track.set_instrument(Tagged(1));
+    track.note(EIGTH, first(4));
+    track.note(QUARTER, second(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+    let time_whole = 2.0;
+
+    let expected_tones = vec![
+        Tone {
+            concrete_values: vec![TET12ConcreteTone(C4)],
+            play_duration: Duration::from_secs_f32(time_whole * (0.25 + 0.0625 + 0.125)),
+            tone_duration: Duration::from_secs_f32(time_whole * (0.25 + 0.0625 + 0.0625)),
+            intensity: 0.5..0.5,
+            ..Default::default()
+        },
+        Tone {
+            concrete_values: vec![TET12ConcreteTone(D4)],
+            play_duration: Duration::from_secs_f32(time_whole * 0.25),
+            tone_duration: Duration::from_secs_f32(time_whole * 0.125),
+            intensity: 0.5..0.5,
+            ..Default::default()
+        },
+    ];
+
+    assert_eq_tones(&result.tones, &expected_tones);
+    assert_eq!(result.instrument_changes, vec![(1, Tagged(1))]);
+}
+
+#[test]
+fn conversion_ties_in_dynamics_change() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_intensity(0.0);
+
+    track.start_dynamic_change();
+    track.note(HALF, first(4)).tie();
+    track.note(QUARTER, first(4));
+    track.note(QUARTER, first(4));
+    track.end_dynamic_change(1.0);
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    assert_eq!(result.len(), 2);
+    assert_eq_f32(result[0].intensity.start, 0.0, 0.0001);
+    assert_eq_f32(result[0].intensity.end, 0.75, 0.0001);
+
+    let dynamics = result[0].dynamics.as_ref().unwrap();
+    assert_eq_f32(dynamics.position.start, 0.0, 0.0001);
+    assert_eq_f32(dynamics.position.end, 0.75, 0.0001);
+}
+
+#[test]
+fn dynamics_change_over_single_note() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_intensity(0.2);
+
+    track.start_dynamic_change();
+    track.note(HALF, first(4));
+    track.end_dynamic_change(1.0);
+    track.note(HALF, second(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    let intensities: Vec<_> = result.iter().map(|tone| tone.intensity.clone()).collect();
+    assert_eq!(intensities, vec![0.2..0.2, 1.0..1.0]);
+}
+
+#[test]
+fn tie_into_pause_is_ignored() {
+    let mut track = UnboundTrack::new(instrument);
+
+    track.note(QUARTER, first(4)).tie();
+    track.pause(QUARTER);
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].concrete_values, vec![TET12ConcreteTone(C4)]);
+    assert!(result[1].concrete_values.is_empty());
+}
+
+#[test]
+fn tie_into_other_pitch_is_ignored() {
+    let mut track = UnboundTrack::new(instrument);
+
+    track.note(QUARTER, first(4)).tie();
+    track.note(QUARTER, second(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].concrete_values, vec![TET12ConcreteTone(C4)]);
+    assert_eq!(result[1].concrete_values, vec![TET12ConcreteTone(D4)]);
+    assert_eq!(result[0].play_duration, Duration::from_millis(500));
+}
+
+#[test]
+fn tie_between_dynamics_changes() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_intensity(1.0);
+
+    // Two separate changes with the same intensities
+    track.start_dynamic_change();
+    track.note(QUARTER, first(4));
+    track.note(QUARTER, first(4)).tie();
+    track.end_dynamic_change(1.0);
+    track.start_dynamic_change();
+    track.note(QUARTER, first(4));
+    track.note(QUARTER, first(4));
+    track.end_dynamic_change(1.0);
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+
+    assert_eq!(result.len(), 3);
+    assert!(result[0].dynamics.is_some());
+    assert!(result[1].dynamics.is_none());
+    assert!(result[2].dynamics.is_some());
+}
+
+#[test]
+fn conversion_parameters() {
+    let mut track = UnboundTrack::new(instrument);
+
+    track.note(QUARTER, first(4));
+    track.set_parameter(parameter::CUTOFF, 1000.0);
+    track.note(QUARTER, first(4));
+    track.note(QUARTER, first(4)).set_parameter(parameter::PAN, -1.0);
+
+    track.start_parameter_change(parameter::CUTOFF);
+    track.note(QUARTER, first(4));
+    track.note(HALF, first(4));
+    track.note(QUARTER, first(4));
+    track.end_parameter_change(parameter::CUTOFF, 3000.0);
+    track.note(QUARTER, first(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info).tones;
+    let cutoff: Vec<_> = result.iter()
+        .map(|tone| tone.parameters.get(parameter::CUTOFF).cloned())
+        .collect();
+
+    let expected = vec![
+        None,
+        Some(1000.0..1000.0),
+        Some(1000.0..1000.0),
+        Some(1000.0..1500.0),
+        Some(1500.0..2500.0),
+        Some(2500.0..3000.0),
+        Some(3000.0..3000.0),
+    ];
+
+    assert_eq!(cutoff, expected);
+
+    assert_eq!(result[2].parameters.get(parameter::PAN), Some(&(-1.0..-1.0)));
+    assert_eq!(result[3].parameters.get(parameter::PAN), None);
+
+    // Reading the parameter at render time
+    let half = result[4].play_duration / 2;
+    assert_eq_f32(result[4].parameter(parameter::CUTOFF, half), 2000.0, 0.01);
+    assert_eq!(result[0].parameter(parameter::CUTOFF, half), parameter::CUTOFF.default_value());
+}
+
+#[test]
+fn instrument_changes() {
+    let mut track = UnboundTrack::new(Tagged(0));
+
+    track.note(QUARTER, first(4));
+    track.set_instrument(Tagged(1));
+    track.note(QUARTER, second(4));
+    track.note(QUARTER, third(4));
+    track.set_instrument(Tagged(2));
+    // Only the last change at the same position is kept
+    track.set_instrument(Tagged(3));
+    track.note(QUARTER, fourth(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    assert_eq!(result.instrument, Tagged(0));
+    assert_eq!(result.instrument_changes, vec![(1, Tagged(1)), (3, Tagged(3))]);
+}
+
+#[test]
+fn instrument_change_before_first_note() {
+    let mut track = UnboundTrack::new(Tagged(0));
+    track.set_instrument(Tagged(1));
+    track.note(QUARTER, first(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    assert_eq!(result.instrument, Tagged(1));
+    assert!(result.instrument_changes.is_empty());
+}
+
+#[test]
+fn conversion_groove() {
+    let mut track = UnboundTrack::new(instrument);
+    track.set_groove(Groove::swing(EIGTH, 75.0));
+
+    sequential_notes!(track, EIGTH, first(4), second(4));
+    track.note(QUARTER, third(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let result = track.convert_to_export_track(info);
+
+    let starts: Vec<u128> = result.tones.iter()
+        .map(|tone| tone.start.unwrap().as_millis())
+        .collect();
+    assert_eq!(starts, vec![0, 375, 500]);
+}
+
 // Utility functions
 
 fn assert_eq_tones<T>(a: &Vec<Tone<T>>, b: &Vec<Tone<T>>)
@@ -279,3 +677,45 @@ fn assert_eq_f32(a: f32, b: f32, epsilon: f32) {
         panic!("assertion failed: {} != {}", a, b);
     }
 }
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Tagged(u8);
+
+impl Instrument for Tagged {
+    type ConcreteValue = TET12ConcreteTone;
+}
+
+#[test]
+fn fortepiano_drops_quickly() {
+    let mut track = UnboundTrack::new(instrument);
+    let table = DynamicsTable::new();
+
+    track.set_dynamic(Dynamic::Fp);
+    track.note(WHOLE, first(4));
+    track.set_dynamic(Dynamic::Fp);
+    track.note(HALF, first(4)).tie();
+    track.note(HALF, first(4));
+
+    let info = SectionInfo {
+        bpm: 120.0,
+        key: music_key::C_MAJOR,
+        settings: &SETTINGS,
+    };
+
+    let tones = track.convert_to_export_track(info).tones;
+    let forte = table.intensity(Dynamic::F).unwrap();
+    let piano = table.intensity(Dynamic::P).unwrap();
+
+    for tone in &tones {
+        assert!((tone.intensity_at(Duration::ZERO) - forte).abs() < 0.0001);
+
+        let during_drop = tone.intensity_at(dynamics::FORTEPIANO_DROP / 2);
+        assert!(during_drop < forte && during_drop > piano);
+
+        // Piano is reached after the drop, not at the end of the note
+        for millis in [100, 500, 1900] {
+            let intensity = tone.intensity_at(Duration::from_millis(millis));
+            assert!((intensity - piano).abs() < 0.0001, "{intensity} at {millis}ms");
+        }
+    }
+}
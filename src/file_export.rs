@@ -1,5 +1,6 @@
 pub mod export_info;
 pub mod wav_export;
+pub mod wav_import;
 
 use std::time::Duration;
 
@@ -14,10 +15,20 @@ const DEFAULT_FADE_OUT: Duration = Duration::from_millis(2);
 /// Represents saving a buffer as a file on the file system.
 pub trait FileExport {
     fn export(&self, buffer: SoundBuffer) -> std::io::Result<()>;
+
+    /// Save a stereo buffer as a file. The default implementation combines
+    /// both channels into mono and calls `export()`.
+    fn export_stereo(&self, buffer: StereoBuffer) -> std::io::Result<()> {
+        self.export(buffer.to_mono())
+    }
 }
 
 /// Renders an `ExportTrack` into a `SoundBuffer`
 /// 
+/// Tones without a `start` time are appended after the previous tone, tones
+/// with a `start` time are mixed in at that time. The instrument always gets
+/// the tone with its `start` time set, so it can tell tones apart.
+/// 
 /// This function will automatically print a progress bar with the render
 /// progress.
 pub fn render<T: Instrument>(track: &ExportTrack<T>, settings: CompositionSettings) -> SoundBuffer {
@@ -33,15 +44,34 @@ pub fn render<T: Instrument>(track: &ExportTrack<T>, settings: CompositionSettin
         progress_bars::add_progress_bar(progress)
     };
 
-    for tone in &track.tones {
+    let mut instrument = &track.instrument;
+    let mut instrument_changes = track.instrument_changes.iter().peekable();
+
+    let starts = start_times(&track.tones);
+
+    for (i, (tone, start)) in track.tones.iter().zip(starts).enumerate() {
         progress.inc(1);
 
+        while let Some((_, next_instrument)) = instrument_changes.next_if(|(index, _)| *index <= i) {
+            instrument = next_instrument;
+        }
+
+        let mut timed_tone = tone.clone();
+        timed_tone.start = Some(start);
+
         let tone_buffer = render_tone(
-            tone,
+            &timed_tone,
             settings,
-            &track.instrument,
+            instrument,
         );
-        buffer.append(tone_buffer);
+
+        match tone.start {
+            Some(start) => {
+                let position = (start.as_secs_f64() * settings.sample_rate as f64).round() as usize;
+                buffer.mix_at(tone_buffer, position);
+            }
+            None => buffer.append(tone_buffer),
+        }
     }
 
     progress.finish_and_clear();
@@ -49,7 +79,7 @@ pub fn render<T: Instrument>(track: &ExportTrack<T>, settings: CompositionSettin
     if let Some(index) = contains_loud_samples(&buffer) {
         let msg = format!(
             "WARNING: Track contains very loud samples starting at sample {}; \
-            t = {:?}. Play back at your own risk.",
+            t = {:?}. Play back at your own risk, or use a `Limiter`.",
             index,
             buffer.time_from_index(index),
         );
@@ -2,14 +2,30 @@ use std::time::Duration;
 use std::ops::Range;
 use crate::instrument::Instrument;
 use crate::composer::MusicKey;
+use crate::composer::parameter::{Parameter, ParameterMap};
+use crate::composer::dynamics::DynamicsChange;
+use crate::composer::Articulation;
+use crate::composer::TimeSignature;
 
 /// The export version of a track. All `MusicTrack` types are able to convert to
 /// this.
 /// 
 /// It only contains the assigned instrument and all the tones of the track.
+/// 
+/// If the instrument changes within the track, `instrument_changes` contains
+/// the index of the first tone that uses the new instrument, together with the
+/// new instrument. The changes are sorted by their index. Tones before the
+/// first change use `instrument`.
+/// 
+/// `time_signatures` contains the time signature of the track with the time it
+/// starts at, and every following change. This isn't needed for rendering, but
+/// for exports that keep the measures (e.g. MIDI). It is empty for tracks
+/// without measures.
 pub struct ExportTrack<T: Instrument> {
     pub tones: Vec<Tone<T::ConcreteValue>>,
     pub instrument: T,
+    pub instrument_changes: Vec<(usize, T)>,
+    pub time_signatures: Vec<(Duration, TimeSignature)>,
 }
 
 /// A raw tone. This is essentailly the export version of a `Note`.
@@ -24,12 +40,34 @@ pub struct ExportTrack<T: Instrument> {
 /// There's also an additional field `beat_emphasis`. If there's a value there,
 /// the note lies on a beat specified in the time signature, and contains the
 /// emphasis level. If there's no value then the note is an offbeat.
+/// 
+/// If the tone is part of a dynamics change, `dynamics` describes the shape of
+/// the change. Use `intensity_at()` to get the intensity at a point in time,
+/// which takes the shape into account.
+/// 
+/// Tones are played one after another. If `start` is set, the tone starts at
+/// this time since the start of the track instead, which lets tones overlap
+/// (e.g. for multiple voices).
+/// 
+/// The `articulation` of the note is passed on, so instruments can change the
+/// attack of the sound. If the tone is slurred with the previous tone,
+/// `slurred_from` contains the values of the previous tone, e.g. for gliding.
+/// 
+/// Instrument parameters are stored like the intensity, as a range from the
+/// value at the beginning to the value at the end. Use `parameter()` to read
+/// them at render time.
+#[derive(Clone)]
 pub struct Tone<T> {
     pub concrete_values: Vec<T>,
     // TODO: Extract all properties into seperate struct
+    pub start: Option<Duration>,
     pub play_duration: Duration,
     pub tone_duration: Duration,
     pub intensity: Range<f32>,
+    pub dynamics: Option<DynamicsChange>,
+    pub articulation: Option<Articulation>,
+    pub slurred_from: Option<Vec<T>>,
+    pub parameters: ParameterMap<Range<f32>>,
 }
 
 /// A collection of important values that are global for the entire composition.
@@ -59,11 +97,67 @@ pub struct SoundBuffer {
     settings: CompositionSettings,
 }
 
+/// A pair of `SoundBuffer`s for the left and right channel.
+/// 
+/// Rendering always happens in mono, stereo buffers are produced by effects
+/// that make use of both channels (e.g. the stereo width of a reverb).
+#[derive(Clone)]
+pub struct StereoBuffer {
+    pub left: SoundBuffer,
+    pub right: SoundBuffer,
+}
+
+impl<T> Tone<T> {
+    /// Get the intensity at the given time since the start of the tone.
+    pub fn intensity_at(&self, time: Duration) -> f32 {
+        let t = if self.play_duration.is_zero() {
+            0.0
+        } else {
+            time.as_secs_f32() / self.play_duration.as_secs_f32()
+        };
+
+        match &self.dynamics {
+            Some(dynamics) => dynamics.intensity_at(t),
+            None => t * (self.intensity.end - self.intensity.start) + self.intensity.start,
+        }
+    }
+
+    /// Get the value of an instrument parameter at the given time since the
+    /// start of the tone. If the parameter was never set, its default value is
+    /// returned.
+    pub fn parameter(&self, key: Parameter, time: Duration) -> f32 {
+        if self.play_duration.is_zero() {
+            return self.parameters.interpolate(key, 0.0);
+        }
+
+        let t = time.as_secs_f32() / self.play_duration.as_secs_f32();
+        self.parameters.interpolate(key, t)
+    }
+}
+
+impl<T> Default for Tone<T> {
+    fn default() -> Self {
+        Self {
+            concrete_values: Vec::new(),
+            start: None,
+            play_duration: Duration::ZERO,
+            tone_duration: Duration::ZERO,
+            intensity: 1.0..1.0,
+            dynamics: None,
+            articulation: None,
+            slurred_from: None,
+            parameters: ParameterMap::new(),
+        }
+    }
+}
+
 impl<T: Instrument> ExportTrack<T> {
     pub fn new(instrument: T) -> Self {
         Self {
             tones: Vec::new(),
             instrument,
+            instrument_changes: Vec::new(),
+            time_signatures: Vec::new(),
         }
     }
 }
@@ -119,6 +213,24 @@ impl SoundBuffer {
         }
     }
 
+    /// Mix the given buffer into the current buffer, starting at the sample
+    /// `position`. The buffer is extended if necessary, and the active samples
+    /// end with the active samples of the given buffer at the latest.
+    pub fn mix_at(&mut self, other: Self, position: usize) {
+        assert_eq!(self.settings, other.settings);
+
+        let end = position + other.samples.len();
+        if self.samples.len() < end {
+            self.samples.resize(end, 0.0);
+        }
+
+        for (i, sample) in other.samples.iter().enumerate() {
+            self.samples[position + i] += sample;
+        }
+
+        self.active_samples = usize::max(self.active_samples, position + other.active_samples);
+    }
+
     /// Append the given buffer to the current buffer.
     /// 
     /// Depending on the internal state, this function might partially mix both
@@ -249,4 +361,65 @@ impl SoundBuffer {
     }
 }
 
+impl StereoBuffer {
+    /// Create a stereo buffer where both channels are a copy of the given
+    /// mono buffer.
+    pub fn from_mono(buffer: SoundBuffer) -> Self {
+        Self {
+            left: buffer.clone(),
+            right: buffer,
+        }
+    }
+
+    /// Combine both channels into a single mono buffer by averaging them.
+    pub fn to_mono(self) -> SoundBuffer {
+        let mut mono = self.left.mix(self.right);
+
+        for sample in mono.samples.iter_mut() {
+            *sample *= 0.5;
+        }
+
+        mono
+    }
+
+    /// Add two stereo buffers together, channel by channel.
+    pub fn mix(self, other: Self) -> Self {
+        Self {
+            left: self.left.mix(other.left),
+            right: self.right.mix(other.right),
+        }
+    }
+
+    /// Append the given buffer to the current buffer, channel by channel.
+    pub fn append(&mut self, other: Self) {
+        self.left.append(other.left);
+        self.right.append(other.right);
+    }
+
+    /// Retrieve the internally saved settings
+    pub fn settings(&self) -> CompositionSettings {
+        self.left.settings
+    }
+
+    /// Retrieve the amount of "active samples". Refer to
+    /// `SoundBuffer::active_samples()` for details.
+    pub fn active_samples(&self) -> usize {
+        self.left.active_samples
+    }
+}
+
+/// The start time of every tone, following the same rules as rendering.
+pub(crate) fn start_times<V>(tones: &[Tone<V>]) -> Vec<Duration> {
+    let mut time = Duration::ZERO;
+    let mut starts = Vec::with_capacity(tones.len());
+
+    for tone in tones {
+        let start = tone.start.unwrap_or(time);
+        starts.push(start);
+        time = start + tone.play_duration;
+    }
+
+    starts
+}
+
 mod tests;
@@ -286,3 +286,35 @@ fn soundbuffer_append_fullmix() {
         assert_eq!(first_buffer.samples[i], expected_result[i]);
     }
 }
+
+// Tests for mix_at()
+
+#[test]
+fn soundbuffer_mix_at_overlap() {
+    let mut first_buffer = SoundBuffer::from_parts(
+        vec![0.1, 0.2, 0.3],
+        3,
+        CompositionSettings::default(),
+    );
+    let second_buffer = SoundBuffer::from_parts(
+        vec![0.4, 0.2, 0.1],
+        2,
+        CompositionSettings::default(),
+    );
+
+    first_buffer.mix_at(second_buffer, 2);
+
+    let expected = SoundBuffer::from_parts(
+        vec![
+            0.1,
+            0.2,
+            0.3 + 0.4,
+            0.2,
+            0.1,
+        ],
+        4,
+        CompositionSettings::default(),
+    );
+
+    assert_soundbuffer_equal(first_buffer, expected);
+}
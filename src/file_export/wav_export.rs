@@ -1,4 +1,4 @@
-use super::{CompositionSettings, FileExport, SoundBuffer};
+use super::{CompositionSettings, FileExport, SoundBuffer, StereoBuffer};
 
 use std::fs::File;
 use std::io::{BufWriter, Write};
@@ -16,6 +16,7 @@ impl WavExport {
         writer: &mut BufWriter<File>,
         settings: CompositionSettings,
         buffer_size: usize,
+        num_channels: u16,
     ) -> std::io::Result<()> {
         use bytemuck::bytes_of;
 
@@ -33,8 +34,6 @@ impl WavExport {
 
         let format_data_length: u32 = 16;
         let format_type: u16 = 1;
-        // TODO: Make this configurable
-        let num_channels: u16 = 1;
 
         let sample_rate_calculation: u32 =
             sample_rate * bits_per_sample as u32 * num_channels as u32 / 8;
@@ -58,6 +57,13 @@ impl WavExport {
 
         Ok(())
     }
+
+    fn write_sample(writer: &mut BufWriter<File>, sample: f32) -> std::io::Result<()> {
+        let amplitude = i16::MAX as f32 * 0.9;
+        // Out of range samples are clipped instead of wrapping around
+        let val = (sample * amplitude).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        writer.write_all(bytemuck::bytes_of(&val))
+    }
 }
 
 impl FileExport for WavExport {
@@ -65,12 +71,30 @@ impl FileExport for WavExport {
         let f = File::create(&self.path)?;
         let mut writer = BufWriter::new(f);
 
-        self.write_header(&mut writer, buffer.settings(), buffer.samples.len() * 2)?;
-        let amplitude = i16::MAX as f32 * 0.9;
+        self.write_header(&mut writer, buffer.settings(), buffer.samples.len() * 2, 1)?;
 
         for sample in buffer.samples {
-            let val = (sample * amplitude).round() as i16;
-            writer.write(bytemuck::bytes_of(&val))?;
+            Self::write_sample(&mut writer, sample)?;
+        }
+
+        Ok(())
+    }
+
+    fn export_stereo(&self, buffer: StereoBuffer) -> std::io::Result<()> {
+        let f = File::create(&self.path)?;
+        let mut writer = BufWriter::new(f);
+
+        let num_samples = usize::max(buffer.left.samples.len(), buffer.right.samples.len());
+
+        self.write_header(&mut writer, buffer.settings(), num_samples * 2 * 2, 2)?;
+
+        // Samples of both channels are interleaved
+        for i in 0..num_samples {
+            let left = buffer.left.samples.get(i).copied().unwrap_or(0.0);
+            let right = buffer.right.samples.get(i).copied().unwrap_or(0.0);
+
+            Self::write_sample(&mut writer, left)?;
+            Self::write_sample(&mut writer, right)?;
         }
 
         Ok(())
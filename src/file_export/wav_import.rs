@@ -0,0 +1,216 @@
+use super::{CompositionSettings, SoundBuffer, StereoBuffer};
+
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read};
+use std::path::PathBuf;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_IEEE_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Imports WAV files into buffers, e.g. recorded impulse responses for a
+/// `Convolution`.
+///
+/// Integer PCM with 8, 16, 24 or 32 bits and 32-bit float samples are
+/// supported. If the sample rate of the file differs from the settings, the
+/// samples are resampled using linear interpolation.
+pub struct WavImport {
+    pub path: PathBuf,
+}
+
+struct WavData {
+    num_channels: usize,
+    sample_rate: u32,
+    channels: Vec<Vec<f32>>,
+}
+
+impl WavImport {
+    /// Read the file into a mono buffer. If the file has multiple channels,
+    /// they are averaged.
+    pub fn import(&self, settings: CompositionSettings) -> std::io::Result<SoundBuffer> {
+        let data = self.read()?;
+
+        let num_samples = data.channels[0].len();
+        let mut mono = vec![0.0; num_samples];
+
+        for channel in &data.channels {
+            for i in 0..num_samples {
+                mono[i] += channel[i] / data.num_channels as f32;
+            }
+        }
+
+        Ok(Self::to_buffer(mono, data.sample_rate, settings))
+    }
+
+    /// Read the file into a stereo buffer. A mono file is copied onto both
+    /// channels, additional channels after the second one are ignored.
+    pub fn import_stereo(&self, settings: CompositionSettings) -> std::io::Result<StereoBuffer> {
+        let data = self.read()?;
+
+        let left = data.channels[0].clone();
+        let right = data.channels.get(1).unwrap_or(&data.channels[0]).clone();
+
+        Ok(StereoBuffer {
+            left: Self::to_buffer(left, data.sample_rate, settings),
+            right: Self::to_buffer(right, data.sample_rate, settings),
+        })
+    }
+
+    fn to_buffer(samples: Vec<f32>, sample_rate: u32, settings: CompositionSettings) -> SoundBuffer {
+        let samples = Self::resample(samples, sample_rate, settings.sample_rate);
+        let active_samples = samples.len();
+
+        SoundBuffer::from_parts(samples, active_samples, settings)
+    }
+
+    /// Resample with linear interpolation from the sample rate `from` to
+    /// `to`.
+    pub(crate) fn resample(samples: Vec<f32>, from: u32, to: u32) -> Vec<f32> {
+        if from == to || samples.is_empty() {
+            return samples;
+        }
+
+        let ratio = from as f64 / to as f64;
+        let num_samples = (samples.len() as f64 / ratio).floor() as usize;
+
+        (0..num_samples).map(|i| {
+            let position = i as f64 * ratio;
+            let index = position.floor() as usize;
+            let t = (position - index as f64) as f32;
+
+            let a = samples[index];
+            let b = samples.get(index + 1).copied().unwrap_or(a);
+            a + (b - a) * t
+        }).collect()
+    }
+
+    fn read(&self) -> std::io::Result<WavData> {
+        let f = File::open(&self.path)?;
+        let mut reader = BufReader::new(f);
+
+        let riff = Self::read_bytes::<4>(&mut reader)?;
+        let _file_size = Self::read_u32(&mut reader)?;
+        let wave = Self::read_bytes::<4>(&mut reader)?;
+
+        if &riff != b"RIFF" || &wave != b"WAVE" {
+            return Err(Self::invalid("Not a WAV file"));
+        }
+
+        let mut format = None;
+
+        loop {
+            let chunk_id = Self::read_bytes::<4>(&mut reader)?;
+            let chunk_size = Self::read_u32(&mut reader)? as usize;
+
+            // The size comes from the file, so only what is actually there is
+            // read instead of allocating the claimed size up front
+            let mut chunk = Vec::new();
+            (&mut reader).take(chunk_size as u64).read_to_end(&mut chunk)?;
+
+            if chunk.len() < chunk_size {
+                return Err(Self::invalid("Chunk is longer than the file"));
+            }
+
+            // Chunks are padded to an even size
+            if chunk_size % 2 == 1 {
+                Self::read_bytes::<1>(&mut reader)?;
+            }
+
+            match &chunk_id {
+                b"fmt " => format = Some(Self::parse_format(&chunk)?),
+                b"data" => {
+                    let (format_type, num_channels, sample_rate, bits_per_sample) =
+                        format.ok_or_else(|| Self::invalid("Data before format chunk"))?;
+
+                    let channels = Self::parse_data(&chunk, format_type, num_channels, bits_per_sample)?;
+
+                    return Ok(WavData {
+                        num_channels,
+                        sample_rate,
+                        channels,
+                    });
+                },
+                _ => (),
+            }
+        }
+    }
+
+    fn parse_format(chunk: &[u8]) -> std::io::Result<(u16, usize, u32, u16)> {
+        if chunk.len() < 16 {
+            return Err(Self::invalid("Format chunk is too short"));
+        }
+
+        let mut format_type = u16::from_le_bytes([chunk[0], chunk[1]]);
+        let num_channels = u16::from_le_bytes([chunk[2], chunk[3]]) as usize;
+        let sample_rate = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        let bits_per_sample = u16::from_le_bytes([chunk[14], chunk[15]]);
+
+        // The actual format is stored in the first bytes of the sub format
+        if format_type == FORMAT_EXTENSIBLE && chunk.len() >= 26 {
+            format_type = u16::from_le_bytes([chunk[24], chunk[25]]);
+        }
+
+        if num_channels == 0 {
+            return Err(Self::invalid("WAV file has no channels"));
+        }
+
+        if sample_rate == 0 {
+            return Err(Self::invalid("WAV file has no sample rate"));
+        }
+
+        Ok((format_type, num_channels, sample_rate, bits_per_sample))
+    }
+
+    fn parse_data(
+        chunk: &[u8],
+        format_type: u16,
+        num_channels: usize,
+        bits_per_sample: u16,
+    ) -> std::io::Result<Vec<Vec<f32>>> {
+        let bytes_per_sample = (bits_per_sample / 8) as usize;
+
+        let decode: fn(&[u8]) -> f32 = match (format_type, bits_per_sample) {
+            (FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+            (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            (FORMAT_PCM, 24) => |b| {
+                let value = i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8;
+                value as f32 / 8388608.0
+            },
+            (FORMAT_PCM, 32) => |b| {
+                i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0
+            },
+            (FORMAT_IEEE_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+
+            _ => return Err(Self::invalid("Unsupported WAV sample format")),
+        };
+
+        let frame_size = bytes_per_sample * num_channels;
+        let num_frames = chunk.len() / frame_size;
+
+        let mut channels = vec![Vec::with_capacity(num_frames); num_channels];
+
+        for frame in chunk.chunks_exact(frame_size) {
+            for (channel, sample) in frame.chunks_exact(bytes_per_sample).enumerate() {
+                channels[channel].push(decode(sample));
+            }
+        }
+
+        Ok(channels)
+    }
+
+    fn read_bytes<const N: usize>(reader: &mut BufReader<File>) -> std::io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_u32(reader: &mut BufReader<File>) -> std::io::Result<u32> {
+        Ok(u32::from_le_bytes(Self::read_bytes::<4>(reader)?))
+    }
+
+    fn invalid(msg: &str) -> Error {
+        Error::new(ErrorKind::InvalidData, msg)
+    }
+}
+
+mod tests;
@@ -0,0 +1,119 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::{FileExport, wav_export::WavExport};
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 44100,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("synth_music_{}_{}.wav", name, std::process::id()))
+}
+
+#[test]
+fn roundtrip_mono() {
+    let path = temp_path("mono");
+    let samples = vec![0.0, 0.5, -0.5, 1.0, -1.0];
+    let buffer = SoundBuffer::from_parts(samples.clone(), samples.len(), SETTINGS);
+
+    WavExport { path: path.clone() }.export(buffer).unwrap();
+    let result = WavImport { path: path.clone() }.import(SETTINGS).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    // WavExport scales the samples to 90%
+    assert_eq!(result.samples.len(), samples.len());
+    assert_eq!(result.active_samples(), samples.len());
+    for i in 0..samples.len() {
+        assert!((result.samples[i] - samples[i] * 0.9).abs() < 0.001);
+    }
+}
+
+#[test]
+fn roundtrip_stereo() {
+    let path = temp_path("stereo");
+    let left = vec![0.1, 0.2, 0.3];
+    let right = vec![-0.1, -0.2, -0.3];
+
+    let buffer = StereoBuffer {
+        left: SoundBuffer::from_parts(left.clone(), 3, SETTINGS),
+        right: SoundBuffer::from_parts(right.clone(), 3, SETTINGS),
+    };
+
+    WavExport { path: path.clone() }.export_stereo(buffer).unwrap();
+    let stereo = WavImport { path: path.clone() }.import_stereo(SETTINGS).unwrap();
+    let mono = WavImport { path: path.clone() }.import(SETTINGS).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    for i in 0..3 {
+        assert!((stereo.left.samples[i] - left[i] * 0.9).abs() < 0.001);
+        assert!((stereo.right.samples[i] - right[i] * 0.9).abs() < 0.001);
+        assert!(mono.samples[i].abs() < 0.001);
+    }
+}
+
+#[test]
+fn resample_sample_rate() {
+    let path = temp_path("resample");
+    let buffer = SoundBuffer::from_parts(vec![0.5; 44100], 44100, SETTINGS);
+
+    WavExport { path: path.clone() }.export(buffer).unwrap();
+
+    let settings = CompositionSettings {
+        sample_rate: 22050,
+    };
+    let result = WavImport { path: path.clone() }.import(settings).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(result.samples.len(), 22050);
+    assert_eq!(result.settings(), settings);
+}
+
+#[test]
+fn invalid_file() {
+    let path = temp_path("invalid");
+    std::fs::write(&path, b"definitely not a wav file").unwrap();
+
+    let result = WavImport { path: path.clone() }.import(SETTINGS);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn oversized_chunk() {
+    let path = temp_path("oversized");
+
+    // A format chunk that claims to be almost 4GB long
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&36_u32.to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&0xFFFF_FFF0_u32.to_le_bytes());
+    bytes.extend_from_slice(&[0; 16]);
+    std::fs::write(&path, bytes).unwrap();
+
+    let result = WavImport { path: path.clone() }.import(SETTINGS);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(matches!(result, Err(error) if error.kind() == ErrorKind::InvalidData));
+}
+
+#[test]
+fn zero_sample_rate() {
+    let path = temp_path("zero_sample_rate");
+    let buffer = SoundBuffer::from_parts(vec![0.5; 16], 16, SETTINGS);
+    WavExport { path: path.clone() }.export(buffer).unwrap();
+
+    // The sample rate is stored after the format type and channel count
+    let mut bytes = std::fs::read(&path).unwrap();
+    assert_eq!(&bytes[12..16], b"fmt ");
+    bytes[24..28].copy_from_slice(&0_u32.to_le_bytes());
+    std::fs::write(&path, bytes).unwrap();
+
+    let result = WavImport { path: path.clone() }.import(SETTINGS);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(matches!(result, Err(error) if error.kind() == ErrorKind::InvalidData));
+}
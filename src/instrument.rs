@@ -2,6 +2,8 @@ pub mod predefined;
 pub mod noise;
 pub mod eq;
 pub mod curve;
+pub mod effects;
+pub mod combinators;
 
 use crate::file_export::Tone;
 use crate::file_export::SoundBuffer;
@@ -14,7 +16,7 @@ use std::time::Duration;
 /// instrument works with. The most common type is the 12-TET note system, that
 /// is defined inside `tet12`.
 pub trait Instrument: Clone {
-    type ConcreteValue: Clone + Copy;
+    type ConcreteValue: Clone + Copy + PartialEq;
 
     /// The main render function that will render all tones playing at the same
     /// time into a single buffer. The default implementation should be
@@ -114,12 +116,9 @@ pub trait Instrument: Clone {
     /// 
     /// The default implementation will have the intensity stay the same as
     /// what it was specified, and interpolate in case it is dynamically
-    /// changing.
+    /// changing, following the shape of the dynamics change.
     fn get_intensity(&self, tones: &Tone<Self::ConcreteValue>, time: Duration) -> f32 {
-        let t = time.as_secs_f32() / tones.play_duration.as_secs_f32();
-        let intensity = &tones.intensity;
-
-        return t * (intensity.end - intensity.start) + intensity.start;
+        tones.intensity_at(time)
     }
 
     /// Is called at the end of the render function, it's possible to make final
@@ -127,3 +126,58 @@ pub trait Instrument: Clone {
     /// does nothing.
     fn post_process(&self, _tones: &Tone<Self::ConcreteValue>, _buffer: &mut SoundBuffer) { }
 }
+
+/// Create an enum that combines several instruments into one, so that a track
+/// can switch between them with `set_instrument()`. All instruments need to
+/// use the same `ConcreteValue`, which is given after the enum name.
+/// 
+/// The created enum implements `Instrument` by forwarding `render()` to the
+/// wrapped instrument.
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// use length::*;
+/// 
+/// instrument_set! {
+///     enum Lead: TET12ConcreteTone {
+///         Soft(predefined::SineGenerator),
+///         Sharp(predefined::SquareGenerator),
+///     }
+/// }
+/// 
+/// let mut track = UnboundTrack::new(Lead::Soft(predefined::SineGenerator));
+/// track.note(QUARTER, first(4));
+/// 
+/// track.set_instrument(Lead::Sharp(predefined::SquareGenerator));
+/// track.note(QUARTER, third(4));
+/// ```
+#[macro_export]
+macro_rules! instrument_set {
+    (
+        $( #[$meta:meta] )*
+        $vis:vis enum $name:ident : $value:ty {
+            $( $variant:ident($instrument:ty) ),+ $(,)?
+        }
+    ) => {
+        $( #[$meta] )*
+        #[derive(Clone)]
+        $vis enum $name {
+            $( $variant($instrument), )+
+        }
+
+        impl $crate::instrument::Instrument for $name {
+            type ConcreteValue = $value;
+
+            fn render(
+                &self,
+                tones: &$crate::file_export::Tone<Self::ConcreteValue>,
+                buffer: &mut $crate::file_export::SoundBuffer,
+            ) {
+                match self {
+                    $( Self::$variant(instrument) => instrument.render(tones, buffer), )+
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,347 @@
+use super::Instrument;
+use crate::file_export::{SoundBuffer, Tone};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use realfft::RealFftPlanner;
+use realfft::num_complex::Complex;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+/// Concrete values that can be shifted by semitones. This is needed for
+/// combinators that play other notes than the ones placed on the track, like
+/// `Octaver`.
+pub trait Transpose: Copy {
+    fn transpose(self, semitones: i32) -> Self;
+}
+
+/// Renders several detuned copies (voices) of the wrapped instrument at once,
+/// which results in a fuller sound.
+///
+/// The voices are spread evenly across the `detune` range given in cents. So
+/// with three voices and a detune of 20 cents, the voices are detuned by -10,
+/// 0, and 10 cents. The phase of every voice is also shifted by a random amount
+/// up to `phase_randomness` of a full cycle, so the voices don't start in
+/// phase. The random phases are generated from the seed and the start time of
+/// the tone, so they change from tone to tone, but the render of a track is
+/// the same every time.
+///
+/// The phase shift rotates every frequency of the voice by the same angle, so
+/// the attack of every voice stays intact. A sine keeps its shape, but the
+/// harmonics of other waveforms (e.g. a saw) no longer line up. This changes
+/// the shape of the waveform and its peak level, while the spectrum, and so
+/// the loudness, stays the same.
+///
+/// Detuning works by resampling the rendered voice, so this works for every
+/// instrument independent of its `ConcreteValue`.
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// let supersaw = Unison::new(predefined::SawGenerator)
+///     .set_voices(7)
+///     .set_detune(30.0)
+///     .set_phase_randomness(0.5);
+///
+/// let track: UnboundTrack<TET12ScaledTone, _> = UnboundTrack::new(supersaw);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Unison<T: Instrument> {
+    instrument: T,
+    voices: usize,
+    detune: f32,
+    phase_randomness: f32,
+    seed: u64,
+}
+
+/// Plays two instruments at the same time. Both instruments need to use the
+/// same `ConcreteValue`.
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// // A sine with a quiet square wave on top
+/// let layered = Layer::new(predefined::SineGenerator, predefined::SquareGenerator)
+///     .set_gains(1.0, 0.2);
+///
+/// let track: UnboundTrack<TET12ScaledTone, _> = UnboundTrack::new(layered);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Layer<A, B>
+where
+    A: Instrument,
+    B: Instrument<ConcreteValue = A::ConcreteValue>,
+{
+    first: A,
+    second: B,
+    first_gain: f32,
+    second_gain: f32,
+}
+
+/// Adds a copy of every tone that is shifted by a number of octaves, e.g. a
+/// sub octave with `-1`.
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// let sub_bass = Octaver::new(predefined::SawGenerator, -1)
+///     .set_gain(0.7);
+///
+/// let track: UnboundTrack<TET12ScaledTone, _> = UnboundTrack::new(sub_bass);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Octaver<T>
+where
+    T: Instrument,
+    T::ConcreteValue: Transpose,
+{
+    instrument: T,
+    octaves: i32,
+    gain: f32,
+}
+
+impl<T: Instrument> Unison<T> {
+    /// Wrap an instrument with two voices detuned by 10 cents.
+    pub fn new(instrument: T) -> Self {
+        Self {
+            instrument,
+            voices: 2,
+            detune: 10.0,
+            phase_randomness: 1.0,
+            seed: 0,
+        }
+    }
+
+    /// Set the amount of voices.
+    pub fn set_voices(mut self, voices: usize) -> Self {
+        self.voices = usize::max(voices, 1);
+        self
+    }
+
+    /// Set the range in cents the voices are spread across.
+    pub fn set_detune(mut self, detune: f32) -> Self {
+        self.detune = detune;
+        self
+    }
+
+    /// Set the maximum phase shift of the voices, from `0.0` (all voices in
+    /// phase) to `1.0` (a full cycle).
+    pub fn set_phase_randomness(mut self, phase_randomness: f32) -> Self {
+        self.phase_randomness = phase_randomness.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the seed for the random phase shifts.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn voice_detune(&self, voice: usize) -> f32 {
+        if self.voices == 1 {
+            return 0.0;
+        }
+
+        let t = voice as f32 / (self.voices - 1) as f32;
+        (t - 0.5) * self.detune
+    }
+}
+
+impl<T: Instrument> Instrument for Unison<T> {
+    type ConcreteValue = T::ConcreteValue;
+
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        let start = tones.start.unwrap_or(Duration::ZERO);
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(start.as_nanos() as u64));
+
+        let mut voice_buffers = Vec::with_capacity(self.voices);
+
+        for voice in 0..self.voices {
+            let ratio = cents_to_ratio(self.voice_detune(voice));
+            let phase = rng.gen_range(0.0 .. 1.0) * self.phase_randomness * TAU;
+
+            // Render enough samples so the resampled voice fills the tone
+            let required_samples = (buffer.active_samples() as f64 * ratio).ceil() as usize + 1;
+
+            let mut voice_buffer = SoundBuffer::from_parts(
+                Vec::new(),
+                required_samples,
+                buffer.settings(),
+            );
+            self.instrument.render(tones, &mut voice_buffer);
+
+            let mut resampled = resample(&voice_buffer.samples, ratio);
+            shift_phase(&mut resampled, phase);
+            for sample in resampled.iter_mut() {
+                *sample /= self.voices as f32;
+            }
+
+            voice_buffers.push(SoundBuffer::from_parts(
+                resampled,
+                buffer.active_samples(),
+                buffer.settings(),
+            ));
+        }
+
+        self.mix_tone_samples(voice_buffers, buffer);
+    }
+}
+
+impl<A, B> Layer<A, B>
+where
+    A: Instrument,
+    B: Instrument<ConcreteValue = A::ConcreteValue>,
+{
+    /// Layer two instruments, both with a gain of `1.0`.
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            first_gain: 1.0,
+            second_gain: 1.0,
+        }
+    }
+
+    /// Set the gain of both instruments.
+    pub fn set_gains(mut self, first_gain: f32, second_gain: f32) -> Self {
+        self.first_gain = first_gain;
+        self.second_gain = second_gain;
+        self
+    }
+}
+
+impl<A, B> Instrument for Layer<A, B>
+where
+    A: Instrument,
+    B: Instrument<ConcreteValue = A::ConcreteValue>,
+{
+    type ConcreteValue = A::ConcreteValue;
+
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        let mut first_buffer = empty_like(buffer);
+        let mut second_buffer = empty_like(buffer);
+
+        self.first.render(tones, &mut first_buffer);
+        self.second.render(tones, &mut second_buffer);
+
+        apply_gain(&mut first_buffer, self.first_gain);
+        apply_gain(&mut second_buffer, self.second_gain);
+
+        self.mix_tone_samples(vec![first_buffer, second_buffer], buffer);
+    }
+}
+
+impl<T> Octaver<T>
+where
+    T: Instrument,
+    T::ConcreteValue: Transpose,
+{
+    /// Add a copy of every tone shifted by the given amount of octaves.
+    pub fn new(instrument: T, octaves: i32) -> Self {
+        Self {
+            instrument,
+            octaves,
+            gain: 1.0,
+        }
+    }
+
+    /// Set the gain of the shifted copy.
+    pub fn set_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+}
+
+impl<T> Instrument for Octaver<T>
+where
+    T: Instrument,
+    T::ConcreteValue: Transpose,
+{
+    type ConcreteValue = T::ConcreteValue;
+
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        let mut shifted_tones = tones.clone();
+        for value in shifted_tones.concrete_values.iter_mut() {
+            *value = value.transpose(self.octaves * 12);
+        }
+
+        let mut original_buffer = empty_like(buffer);
+        let mut shifted_buffer = empty_like(buffer);
+
+        self.instrument.render(tones, &mut original_buffer);
+        self.instrument.render(&shifted_tones, &mut shifted_buffer);
+
+        apply_gain(&mut shifted_buffer, self.gain);
+
+        self.mix_tone_samples(vec![original_buffer, shifted_buffer], buffer);
+    }
+}
+
+fn empty_like(buffer: &SoundBuffer) -> SoundBuffer {
+    SoundBuffer::from_parts(Vec::new(), buffer.active_samples(), buffer.settings())
+}
+
+fn apply_gain(buffer: &mut SoundBuffer, gain: f32) {
+    for sample in buffer.samples.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+fn cents_to_ratio(cents: f32) -> f64 {
+    2_f64.powf(cents as f64 / 1200.0)
+}
+
+/// Read the samples at a different speed, which shifts the pitch by `ratio`.
+fn resample(samples: &[f32], ratio: f64) -> Vec<f32> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let num_samples = ((samples.len() - 1) as f64 / ratio).floor() as usize + 1;
+
+    (0..num_samples).map(|i| {
+        let position = i as f64 * ratio;
+        let index = position.floor() as usize;
+        let t = (position - index as f64) as f32;
+
+        let a = samples[index];
+        let b = samples.get(index + 1).copied().unwrap_or(a);
+        a + (b - a) * t
+    }).collect()
+}
+
+/// Shift the phase of every frequency in the samples by `phase` radians. The
+/// envelope of the samples is kept, unlike shifting them in time. The samples
+/// are padded with silence, so the end doesn't wrap around into the start.
+/// Only a sine is shifted like a delay, other waveforms change their shape.
+fn shift_phase(samples: &mut [f32], phase: f32) {
+    if samples.is_empty() || phase == 0.0 {
+        return;
+    }
+
+    let fft_size = (samples.len() * 2).next_power_of_two();
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(fft_size);
+    let inverse = planner.plan_fft_inverse(fft_size);
+
+    let mut input = samples.to_vec();
+    input.resize(fft_size, 0.0);
+    let mut spectrum = forward.make_output_vec();
+    forward.process(&mut input, &mut spectrum).unwrap();
+
+    // The DC and Nyquist bins have to stay real
+    let rotation = Complex::from_polar(1.0, phase);
+    let last = spectrum.len() - 1;
+    for bin in spectrum[1..last].iter_mut() {
+        *bin *= rotation;
+    }
+
+    let mut output = inverse.make_output_vec();
+    inverse.process(&mut spectrum, &mut output).unwrap();
+
+    for (sample, shifted) in samples.iter_mut().zip(output) {
+        *sample = shifted / fft_size as f32;
+    }
+}
+
+mod tests;
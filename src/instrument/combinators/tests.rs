@@ -0,0 +1,178 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::CompositionSettings;
+use crate::instrument::predefined::{SineGenerator, SquareGenerator, SawGenerator};
+use crate::instrument::predefined::tet12::TET12ConcreteTone;
+use std::time::Duration;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 8000,
+};
+
+fn tone(values: Vec<i32>) -> Tone<TET12ConcreteTone> {
+    Tone {
+        concrete_values: values.into_iter().map(TET12ConcreteTone).collect(),
+        play_duration: Duration::from_millis(100),
+        tone_duration: Duration::from_millis(100),
+        intensity: 1.0..1.0,
+        ..Default::default()
+    }
+}
+
+fn render<T: Instrument>(instrument: &T, tones: &Tone<T::ConcreteValue>) -> Vec<f32> {
+    let mut buffer = SoundBuffer::from_parts(Vec::new(), 800, SETTINGS);
+    instrument.render(tones, &mut buffer);
+    buffer.samples
+}
+
+fn assert_samples_eq(a: &[f32], b: &[f32]) {
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b) {
+        assert!((x - y).abs() < 1e-4, "{x} != {y}");
+    }
+}
+
+#[test]
+fn transpose_tet12() {
+    assert_eq!(TET12ConcreteTone(0).transpose(-12), TET12ConcreteTone(-12));
+    assert_eq!(TET12ConcreteTone(3).transpose(7), TET12ConcreteTone(10));
+}
+
+#[test]
+fn layer_sums_instruments() {
+    let tones = tone(vec![0]);
+    let sine = render(&SineGenerator, &tones);
+    let square = render(&SquareGenerator, &tones);
+
+    let layered = render(&Layer::new(SineGenerator, SquareGenerator).set_gains(1.0, 0.5), &tones);
+
+    let expected: Vec<f32> = sine.iter().zip(&square).map(|(a, b)| a + 0.5 * b).collect();
+    assert_samples_eq(&layered, &expected);
+}
+
+#[test]
+fn octaver_adds_shifted_tones() {
+    let octaved = render(&Octaver::new(SineGenerator, -1).set_gain(0.5), &tone(vec![0, 4]));
+
+    let original = render(&SineGenerator, &tone(vec![0, 4]));
+    let shifted = render(&SineGenerator, &tone(vec![-12, -8]));
+
+    let expected: Vec<f32> = original.iter().zip(&shifted).map(|(a, b)| a + 0.5 * b).collect();
+    assert_samples_eq(&octaved, &expected);
+}
+
+#[test]
+fn single_voice_unison_is_unchanged() {
+    let tones = tone(vec![0]);
+    let unison = Unison::new(SineGenerator)
+        .set_voices(1)
+        .set_phase_randomness(0.0);
+
+    let rendered = render(&unison, &tones);
+    let expected = render(&SineGenerator, &tones);
+
+    assert_samples_eq(&rendered[..800], &expected);
+}
+
+#[test]
+fn unison_detunes_voices() {
+    // The voices are detuned by -600 and 600 cents, which is 6 semitones
+    let unison = Unison::new(SineGenerator)
+        .set_voices(2)
+        .set_detune(1200.0)
+        .set_phase_randomness(0.0);
+
+    let rendered = render(&unison, &tone(vec![0]));
+
+    let low = render(&SineGenerator, &tone(vec![-6]));
+    let high = render(&SineGenerator, &tone(vec![6]));
+
+    // Resampling with linear interpolation is not exact
+    for i in 0..800 {
+        let expected = (low[i] + high[i]) / 2.0;
+        assert!((rendered[i] - expected).abs() < 0.05, "sample {i}: {} != {expected}", rendered[i]);
+    }
+}
+
+#[test]
+fn unison_is_deterministic() {
+    let tones = tone(vec![0]);
+    let unison = Unison::new(SineGenerator).set_voices(4).set_seed(42);
+
+    assert_eq!(render(&unison.clone(), &tones), render(&unison.clone(), &tones));
+    assert_ne!(render(&unison.clone(), &tones), render(&unison.set_seed(7), &tones));
+}
+
+#[test]
+fn unison_changes_phases_between_tones() {
+    let first = tone(vec![0]);
+    let second = Tone {
+        start: Some(Duration::from_millis(100)),
+        ..tone(vec![0])
+    };
+    let unison = Unison::new(SineGenerator).set_voices(4).set_seed(42);
+
+    // The phases only depend on the tone, not on the render order
+    assert_eq!(render(&unison, &second), render(&unison, &second));
+    assert_ne!(render(&unison, &first), render(&unison, &second));
+}
+
+#[test]
+fn unison_is_sync() {
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<Unison<SineGenerator>>();
+}
+
+#[test]
+fn unison_shifts_phase_not_time() {
+    let tones = tone(vec![0]);
+    let unison = Unison::new(Delayed)
+        .set_voices(1)
+        .set_phase_randomness(1.0)
+        .set_seed(3);
+
+    let rendered = render(&unison, &tones);
+    let unshifted = render(&Delayed, &tones);
+    assert_ne!(rendered, unshifted);
+
+    // The tone still starts at the same time
+    for (i, sample) in rendered[..300].iter().enumerate() {
+        assert!(sample.abs() < 0.05, "sample {i} is {sample} before the attack");
+    }
+
+    // The shifted sine keeps its amplitude
+    let peak = rendered[500..700].iter().fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+    assert!((peak - 1.0).abs() < 0.05, "peak is {peak}");
+}
+
+#[test]
+fn phase_shift_changes_saw_shape() {
+    let saw = render(&SawGenerator, &tone(vec![0]));
+    let mut shifted = saw.clone();
+    shift_phase(&mut shifted, TAU / 4.0);
+
+    let peak = |samples: &[f32]| samples[100..700].iter().fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+    let energy = |samples: &[f32]| samples[100..700].iter().map(|sample| sample * sample).sum::<f32>();
+
+    // The harmonics don't line up anymore, so the peak changes while the
+    // loudness stays the same
+    assert!((peak(&shifted) - peak(&saw)).abs() > 0.1, "{} and {}", peak(&shifted), peak(&saw));
+    assert!((energy(&shifted) / energy(&saw) - 1.0).abs() < 0.05);
+}
+
+/// A sine that is silent for the first 50ms of the tone.
+#[derive(Clone, Copy, Debug)]
+struct Delayed;
+
+impl Instrument for Delayed {
+    type ConcreteValue = TET12ConcreteTone;
+
+    fn render_sample(&self, tone: TET12ConcreteTone, time: Duration) -> f32 {
+        if time < Duration::from_millis(50) {
+            return 0.0;
+        }
+
+        SineGenerator.render_sample(tone, time)
+    }
+}
@@ -8,7 +8,10 @@ pub trait Curve {
     /// Return a closure where the input is x and the output is the computed y
     /// value. The default implementation calls `get(x)` and probably does not
     /// need to be implemented manually.
-    fn into_closure(&self) -> impl Fn(f32) -> f32 {
+    fn into_closure(&self) -> impl Fn(f32) -> f32
+    where
+        Self: Sized,
+    {
         move |x| {
             self.get(x)
         }
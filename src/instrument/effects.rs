@@ -0,0 +1,53 @@
+pub mod reverb;
+pub mod convolution;
+pub mod delay;
+pub mod dynamics;
+pub mod distortion;
+pub mod modulation;
+
+use crate::file_export::{SoundBuffer, StereoBuffer};
+
+#[doc(inline)]
+pub use reverb::Reverb;
+#[doc(inline)]
+pub use convolution::Convolution;
+#[doc(inline)]
+pub use delay::Delay;
+#[doc(inline)]
+pub use dynamics::{Compressor, Limiter};
+#[doc(inline)]
+pub use distortion::{Waveshaper, Bitcrusher};
+#[doc(inline)]
+pub use modulation::{ModulatedDelay, Phaser};
+
+/// Implementors process an already rendered `SoundBuffer`, e.g. a whole
+/// section, a single track, or a single tone inside `Instrument::post_process`.
+///
+/// Effects may produce more samples than the buffer had before (e.g. the tail
+/// of a reverb). These additional samples are placed after the active samples,
+/// so they get mixed into the following buffer when appending instead of being
+/// cut off.
+pub trait Effect {
+    /// Apply the effect on a mono buffer.
+    fn apply(&self, buffer: &mut SoundBuffer);
+
+    /// Apply the effect on a stereo buffer. Override this if the effect makes
+    /// use of both channels (e.g. for stereo width).
+    ///
+    /// The default implementation applies the effect on both channels
+    /// independently.
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        self.apply(&mut buffer.left);
+        self.apply(&mut buffer.right);
+    }
+}
+
+/// Convert a level in dB into an amplitude, where 0dB is an amplitude of `1.0`.
+pub fn db_to_amplitude(db: f32) -> f32 {
+    10_f32.powf(db / 20.0)
+}
+
+/// Convert an amplitude into a level in dB, where an amplitude of `1.0` is 0dB.
+pub fn amplitude_to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.log10()
+}
@@ -0,0 +1,228 @@
+use super::Effect;
+use crate::file_export::{SoundBuffer, CompositionSettings};
+use crate::file_export::wav_import::WavImport;
+
+use realfft::RealFftPlanner;
+use realfft::num_complex::Complex;
+use std::collections::VecDeque;
+
+const DEFAULT_PARTITION_SIZE: usize = 1024;
+
+/// A convolution effect, e.g. for a convolution reverb. The buffer is
+/// convolved with an impulse response, which can be a recorded room loaded
+/// with [`crate::file_export::wav_import::WavImport`].
+///
+/// The convolution is computed using uniformly partitioned FFT convolution, so
+/// long impulse responses stay fast. The impulse response is split into
+/// partitions of `partition_size` samples, which are transformed once when
+/// constructing the effect.
+///
+/// The result is longer than the input by the length of the impulse response.
+/// These additional samples are placed after the active samples, so they get
+/// mixed into the following buffer when appending.
+///
+/// ```no_run
+/// use synth_music::prelude::*;
+/// use std::path::PathBuf;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = SoundBuffer::new(settings);
+/// #
+/// let impulse_response = WavImport {
+///     path: PathBuf::from("cathedral.wav"),
+/// }.import(settings).unwrap();
+///
+/// let reverb = Convolution::new(impulse_response)
+///     .set_wet(0.4)
+///     .set_dry(0.8);
+///
+/// reverb.apply(&mut buffer);
+/// ```
+#[derive(Clone)]
+pub struct Convolution {
+    impulse_response: SoundBuffer,
+    partitions: Vec<Vec<Complex<f32>>>,
+    partition_size: usize,
+    wet: f32,
+    dry: f32,
+}
+
+impl Convolution {
+    /// Create a new convolution effect with the given impulse response. An
+    /// impulse response with another sample rate than the buffer is resampled
+    /// every time the effect is applied, so it's faster to import it with the
+    /// settings of the composition.
+    pub fn new(impulse_response: SoundBuffer) -> Self {
+        let mut convolution = Self {
+            impulse_response,
+            partitions: Vec::new(),
+            partition_size: DEFAULT_PARTITION_SIZE,
+            wet: 1.0,
+            dry: 0.0,
+        };
+
+        convolution.compute_partitions();
+        convolution
+    }
+
+    /// Set the amount of samples of a single partition. Smaller partitions
+    /// mean more FFTs for long impulse responses, larger partitions mean more
+    /// computation for short inputs.
+    ///
+    /// Will panic if `partition_size` is zero.
+    pub fn set_partition_size(mut self, partition_size: usize) -> Self {
+        if partition_size == 0 {
+            panic!("The partition size must not be zero.");
+        }
+
+        self.partition_size = partition_size;
+        self.compute_partitions();
+        self
+    }
+
+    /// Set the amplitude of the convolved signal.
+    pub fn set_wet(mut self, wet: f32) -> Self {
+        self.wet = wet;
+        self
+    }
+
+    /// Set the amplitude of the original signal.
+    pub fn set_dry(mut self, dry: f32) -> Self {
+        self.dry = dry;
+        self
+    }
+
+    /// Return the amount of samples that are added to the end of the buffer.
+    pub fn tail_samples(&self) -> usize {
+        self.impulse_response.samples.len().saturating_sub(1)
+    }
+
+    /// A copy of the effect with the impulse response resampled to the
+    /// sample rate of `settings`. The impulse response is scaled, so the
+    /// convolution keeps its gain with more or fewer samples.
+    fn resampled(&self, settings: CompositionSettings) -> Self {
+        let from = self.impulse_response.settings().sample_rate;
+        let gain = from as f32 / settings.sample_rate as f32;
+
+        let samples: Vec<f32> = WavImport::resample(self.impulse_response.samples.clone(), from, settings.sample_rate)
+            .into_iter()
+            .map(|sample| sample * gain)
+            .collect();
+        let active_samples = samples.len();
+
+        let mut convolution = Self {
+            impulse_response: SoundBuffer::from_parts(samples, active_samples, settings),
+            partitions: Vec::new(),
+            partition_size: self.partition_size,
+            wet: self.wet,
+            dry: self.dry,
+        };
+
+        convolution.compute_partitions();
+        convolution
+    }
+
+    fn fft_len(&self) -> usize {
+        self.partition_size * 2
+    }
+
+    fn compute_partitions(&mut self) {
+        let fft_len = self.fft_len();
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft_forward = planner.plan_fft_forward(fft_len);
+
+        self.partitions = self.impulse_response.samples
+            .chunks(self.partition_size)
+            .map(|chunk| {
+                let mut input = vec![0.0; fft_len];
+                input[..chunk.len()].copy_from_slice(chunk);
+
+                let mut spectrum = fft_forward.make_output_vec();
+                fft_forward.process(&mut input, &mut spectrum).unwrap();
+                spectrum
+            })
+            .collect();
+    }
+
+    fn convolve(&self, input: &[f32]) -> Vec<f32> {
+        if input.is_empty() || self.partitions.is_empty() {
+            return vec![0.0; input.len()];
+        }
+
+        let partition_size = self.partition_size;
+        let fft_len = self.fft_len();
+        let output_len = input.len() + self.tail_samples();
+        let num_blocks = output_len.div_ceil(partition_size);
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft_forward = planner.plan_fft_forward(fft_len);
+        let fft_inverse = planner.plan_fft_inverse(fft_len);
+
+        // Spectra of the most recent input blocks, the newest one first
+        let mut delay_line: VecDeque<Vec<Complex<f32>>> = VecDeque::with_capacity(self.partitions.len());
+
+        let mut output = vec![0.0; num_blocks * partition_size + partition_size];
+        let mut time_block = vec![0.0; fft_len];
+        let mut accumulator = fft_forward.make_output_vec();
+
+        for block in 0..num_blocks {
+            let start = block * partition_size;
+            let end = usize::min(start + partition_size, input.len());
+
+            time_block.fill(0.0);
+            if start < end {
+                time_block[..end - start].copy_from_slice(&input[start..end]);
+            }
+
+            let mut spectrum = fft_forward.make_output_vec();
+            fft_forward.process(&mut time_block, &mut spectrum).unwrap();
+
+            delay_line.push_front(spectrum);
+            delay_line.truncate(self.partitions.len());
+
+            accumulator.fill(Complex::new(0.0, 0.0));
+            for (input_spectrum, partition) in delay_line.iter().zip(self.partitions.iter()) {
+                for i in 0..accumulator.len() {
+                    accumulator[i] += input_spectrum[i] * partition[i];
+                }
+            }
+
+            // A real signal has no imaginary part at DC and Nyquist frequency
+            let last = accumulator.len() - 1;
+            accumulator[0].im = 0.0;
+            accumulator[last].im = 0.0;
+
+            fft_inverse.process(&mut accumulator, &mut time_block).unwrap();
+
+            for i in 0..fft_len {
+                output[start + i] += time_block[i] / fft_len as f32;
+            }
+        }
+
+        output.truncate(output_len);
+        output
+    }
+}
+
+impl Effect for Convolution {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        if buffer.settings() != self.impulse_response.settings() {
+            self.resampled(buffer.settings()).apply(buffer);
+            return;
+        }
+
+        let mut output = self.convolve(&buffer.samples);
+
+        for (i, sample) in output.iter_mut().enumerate() {
+            let dry_sample = buffer.samples.get(i).copied().unwrap_or(0.0);
+            *sample = *sample * self.wet + dry_sample * self.dry;
+        }
+
+        buffer.samples = output;
+    }
+}
+
+mod tests;
@@ -0,0 +1,112 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::CompositionSettings;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 44100,
+};
+
+fn buffer(samples: Vec<f32>) -> SoundBuffer {
+    let active_samples = samples.len();
+    SoundBuffer::from_parts(samples, active_samples, SETTINGS)
+}
+
+fn direct_convolution(a: &[f32], b: &[f32]) -> Vec<f32> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+
+    for i in 0..a.len() {
+        for j in 0..b.len() {
+            result[i + j] += a[i] * b[j];
+        }
+    }
+
+    result
+}
+
+fn test_signal(num_samples: usize, seed: usize) -> Vec<f32> {
+    (0..num_samples)
+        .map(|i| (((i * 7919 + seed * 104729) % 1000) as f32 / 500.0) - 1.0)
+        .collect()
+}
+
+#[test]
+fn identity() {
+    let input = test_signal(100, 0);
+    let mut result = buffer(input.clone());
+
+    Convolution::new(buffer(vec![1.0]))
+        .set_partition_size(16)
+        .apply(&mut result);
+
+    assert_eq!(result.samples.len(), input.len());
+    for i in 0..input.len() {
+        assert!((result.samples[i] - input[i]).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn matches_direct_convolution() {
+    let input = test_signal(300, 1);
+    let impulse_response = test_signal(70, 2);
+
+    let expected = direct_convolution(&input, &impulse_response);
+
+    for partition_size in [1, 7, 16, 64, 1024] {
+        let mut result = buffer(input.clone());
+
+        Convolution::new(buffer(impulse_response.clone()))
+            .set_partition_size(partition_size)
+            .apply(&mut result);
+
+        assert_eq!(result.samples.len(), expected.len());
+        for i in 0..expected.len() {
+            assert!((result.samples[i] - expected[i]).abs() < 0.001);
+        }
+    }
+}
+
+#[test]
+fn tail_after_active_samples() {
+    let mut result = buffer(vec![1.0, 0.0, 0.0]);
+
+    Convolution::new(buffer(vec![0.5, 0.25, 0.125, 0.0625]))
+        .apply(&mut result);
+
+    assert_eq!(result.active_samples(), 3);
+    assert_eq!(result.samples.len(), 6);
+    assert!((result.samples[3] - 0.0625).abs() < 0.0001);
+}
+
+#[test]
+fn wet_dry() {
+    let input = vec![1.0, 0.5];
+    let mut result = buffer(input);
+
+    Convolution::new(buffer(vec![0.0, 1.0]))
+        .set_wet(0.5)
+        .set_dry(1.0)
+        .apply(&mut result);
+
+    let expected = [1.0, 0.5 + 0.5, 0.25];
+    for i in 0..expected.len() {
+        assert!((result.samples[i] - expected[i]).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn resamples_impulse_response() {
+    let settings = CompositionSettings {
+        sample_rate: 22050,
+    };
+    let impulse_response = SoundBuffer::from_parts(vec![1.0], 1, settings);
+
+    let mut result = buffer(vec![1.0; 64]);
+    Convolution::new(impulse_response).apply(&mut result);
+
+    // The impulse response is spread over two samples with half the gain
+    assert_eq!(result.samples.len(), 65);
+    for sample in &result.samples[1..64] {
+        assert!((sample - 1.0).abs() < 1e-4, "{sample}");
+    }
+}
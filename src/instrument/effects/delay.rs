@@ -0,0 +1,233 @@
+use super::Effect;
+use crate::composer::Length;
+use crate::file_export::{SoundBuffer, StereoBuffer};
+
+use std::time::Duration;
+
+// The tail is rendered until the echoes have decayed by 60dB
+const TAIL_DECAY: f32 = 0.001;
+const MAX_FEEDBACK: f32 = 0.99;
+
+/// A delay (echo) effect with feedback. Every echo is fed back into the delay
+/// line, passing a lowpass filter so that later echoes become darker.
+///
+/// The delay time is either given as a duration, or as a note length that is
+/// synced to the tempo of the section. With ping-pong enabled, the echoes
+/// alternate between the left and the right channel when applied on a
+/// `StereoBuffer`.
+///
+/// The echoes are not truncated at the end of the buffer. The buffer is
+/// extended after its active samples, so they will be mixed into the
+/// following buffer when appending.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use length::*;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = SoundBuffer::from_parts(vec![1.0; 100], 100, settings);
+/// # let section_info = SectionInfo {
+/// #     bpm: 120.0,
+/// #     key: music_key::C_MAJOR,
+/// #     settings: &settings,
+/// # };
+/// #
+/// // Dotted eighth echoes
+/// let delay = Delay::synced(EIGTH.dot(), section_info.bpm)
+///     .set_feedback(0.4)
+///     .set_damping(0.3)
+///     .set_wet(0.5);
+///
+/// delay.apply(&mut buffer);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Delay {
+    delay_time: Duration,
+    feedback: f32,
+    damping: f32,
+    ping_pong: bool,
+    wet: f32,
+    dry: f32,
+}
+
+impl Delay {
+    /// Create a new delay where the echoes repeat after `delay_time`.
+    pub fn new(delay_time: Duration) -> Self {
+        Self {
+            delay_time,
+            feedback: 0.3,
+            damping: 0.0,
+            ping_pong: false,
+            wet: 0.5,
+            dry: 1.0,
+        }
+    }
+
+    /// Create a new delay where the echoes repeat after a note length, given
+    /// the tempo in BPM (e.g. from `SectionInfo`).
+    pub fn synced(length: Length, bpm: f32) -> Self {
+        Self::new(length.to_duration(bpm))
+    }
+
+    /// Set how much of an echo is fed back into the delay line. A value of
+    /// `0.0` will result in a single echo. The value is limited to be smaller
+    /// than `1.0`, so that the echoes always decay.
+    pub fn set_feedback(mut self, feedback: f32) -> Self {
+        self.feedback = feedback.clamp(0.0, MAX_FEEDBACK);
+        self
+    }
+
+    /// Set how much high frequencies are absorbed for every repetition in the
+    /// range from `0.0` to `1.0`.
+    pub fn set_damping(mut self, damping: f32) -> Self {
+        self.damping = damping.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Let the echoes alternate between the left and the right channel. This
+    /// only has an effect when applied on a `StereoBuffer`.
+    pub fn set_ping_pong(mut self, ping_pong: bool) -> Self {
+        self.ping_pong = ping_pong;
+        self
+    }
+
+    /// Set the amplitude of the echoes.
+    pub fn set_wet(mut self, wet: f32) -> Self {
+        self.wet = wet;
+        self
+    }
+
+    /// Set the amplitude of the original signal.
+    pub fn set_dry(mut self, dry: f32) -> Self {
+        self.dry = dry;
+        self
+    }
+
+    /// Compute the amount of samples the echoes keep going after the input has
+    /// become silent.
+    pub fn tail_samples(&self, sample_rate: u32) -> usize {
+        let delay_samples = self.delay_samples(sample_rate);
+
+        let repetitions = if self.feedback > 0.0 {
+            (TAIL_DECAY.ln() / self.feedback.ln()).ceil() as usize
+        } else {
+            0
+        };
+
+        // Ping-pong needs an additional repetition to reach the other channel
+        delay_samples * (repetitions + 2)
+    }
+
+    fn delay_samples(&self, sample_rate: u32) -> usize {
+        let samples = (self.delay_time.as_secs_f64() * sample_rate as f64).round() as usize;
+        usize::max(samples, 1)
+    }
+
+    fn output_len(&self, buffer: &SoundBuffer) -> usize {
+        buffer.samples.len() + self.tail_samples(buffer.settings().sample_rate)
+    }
+}
+
+impl Effect for Delay {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let delay_samples = self.delay_samples(buffer.settings().sample_rate);
+        let output_len = self.output_len(buffer);
+
+        let mut line = FeedbackLine::new(delay_samples, self.damping);
+        let mut output = Vec::with_capacity(output_len);
+
+        for i in 0..output_len {
+            let input = buffer.samples.get(i).copied().unwrap_or(0.0);
+
+            let echo = line.read();
+            line.write(input + line.filtered() * self.feedback);
+
+            output.push(input * self.dry + echo * self.wet);
+        }
+
+        buffer.samples = output;
+    }
+
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        let delay_samples = self.delay_samples(buffer.settings().sample_rate);
+        let output_len = usize::max(
+            self.output_len(&buffer.left),
+            self.output_len(&buffer.right),
+        );
+
+        let mut line_left = FeedbackLine::new(delay_samples, self.damping);
+        let mut line_right = FeedbackLine::new(delay_samples, self.damping);
+
+        let mut output_left = Vec::with_capacity(output_len);
+        let mut output_right = Vec::with_capacity(output_len);
+
+        for i in 0..output_len {
+            let input_left = buffer.left.samples.get(i).copied().unwrap_or(0.0);
+            let input_right = buffer.right.samples.get(i).copied().unwrap_or(0.0);
+
+            let echo_left = line_left.read();
+            let echo_right = line_right.read();
+
+            let feedback_left = line_left.filtered() * self.feedback;
+            let feedback_right = line_right.filtered() * self.feedback;
+
+            if self.ping_pong {
+                // The input enters on the left, and every echo switches sides
+                let input = (input_left + input_right) / 2.0;
+                line_left.write(input + feedback_right);
+                line_right.write(feedback_left);
+            }
+            else {
+                line_left.write(input_left + feedback_left);
+                line_right.write(input_right + feedback_right);
+            }
+
+            output_left.push(input_left * self.dry + echo_left * self.wet);
+            output_right.push(input_right * self.dry + echo_right * self.wet);
+        }
+
+        buffer.left.samples = output_left;
+        buffer.right.samples = output_right;
+    }
+}
+
+/// A circular delay line with a lowpass filter on its output.
+struct FeedbackLine {
+    buffer: Vec<f32>,
+    index: usize,
+    damping: f32,
+    filter_store: f32,
+}
+
+impl FeedbackLine {
+    fn new(size: usize, damping: f32) -> Self {
+        Self {
+            buffer: vec![0.0; size],
+            index: 0,
+            damping,
+            filter_store: 0.0,
+        }
+    }
+
+    /// Read the delayed sample and update the lowpass filter with it.
+    fn read(&mut self) -> f32 {
+        let output = self.buffer[self.index];
+        self.filter_store = output * (1.0 - self.damping) + self.filter_store * self.damping;
+        output
+    }
+
+    /// The lowpass filtered version of the most recently read sample.
+    fn filtered(&self) -> f32 {
+        self.filter_store
+    }
+
+    /// Write a new sample into the line, replacing the most recently read one.
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.index] = sample;
+        self.index = (self.index + 1) % self.buffer.len();
+    }
+}
+
+mod tests;
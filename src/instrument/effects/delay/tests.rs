@@ -0,0 +1,100 @@
+#![cfg(test)]
+
+use super::*;
+use crate::composer::length::*;
+use crate::file_export::CompositionSettings;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 1000,
+};
+
+fn impulse(num_samples: usize) -> SoundBuffer {
+    let mut samples = vec![0.0; num_samples];
+    samples[0] = 1.0;
+
+    SoundBuffer::from_parts(samples, num_samples, SETTINGS)
+}
+
+#[test]
+fn single_echo() {
+    let mut buffer = impulse(10);
+
+    Delay::new(Duration::from_millis(5))
+        .set_feedback(0.0)
+        .set_wet(0.5)
+        .apply(&mut buffer);
+
+    assert_eq!(buffer.active_samples(), 10);
+    assert_eq!(buffer.samples[0], 1.0);
+    assert_eq!(buffer.samples[5], 0.5);
+
+    let total: f32 = buffer.samples.iter().sum();
+    assert_eq!(total, 1.5);
+}
+
+#[test]
+fn feedback_repeats() {
+    let mut buffer = impulse(10);
+
+    Delay::new(Duration::from_millis(4))
+        .set_feedback(0.5)
+        .set_wet(1.0)
+        .set_dry(0.0)
+        .apply(&mut buffer);
+
+    assert_eq!(buffer.samples[4], 1.0);
+    assert_eq!(buffer.samples[8], 0.5);
+    assert_eq!(buffer.samples[12], 0.25);
+    assert_eq!(buffer.samples[16], 0.125);
+
+    // Echoes continue after the active samples
+    assert!(buffer.samples.len() > 16);
+    assert!(buffer.samples[buffer.samples.len() - 1].abs() < 0.001);
+}
+
+#[test]
+fn damping_darkens_echoes() {
+    let mut damped = impulse(10);
+    let mut undamped = impulse(10);
+
+    let delay = Delay::new(Duration::from_millis(4))
+        .set_feedback(0.5)
+        .set_dry(0.0);
+
+    delay.set_damping(0.5).apply(&mut damped);
+    delay.set_damping(0.0).apply(&mut undamped);
+
+    // The first echo is not affected by the feedback path
+    assert_eq!(damped.samples[4], undamped.samples[4]);
+    assert!(damped.samples[8] < undamped.samples[8]);
+}
+
+#[test]
+fn synced_to_tempo() {
+    let delay = Delay::synced(EIGTH.dot(), 120.0);
+
+    // A dotted eighth at 120 BPM is 375ms long
+    assert_eq!(delay.delay_samples(1000), 375);
+    assert_eq!(delay.delay_samples(48000), 18000);
+}
+
+#[test]
+fn ping_pong() {
+    let mut buffer = StereoBuffer::from_mono(impulse(10));
+
+    Delay::new(Duration::from_millis(3))
+        .set_feedback(0.5)
+        .set_wet(1.0)
+        .set_dry(0.0)
+        .set_ping_pong(true)
+        .apply_stereo(&mut buffer);
+
+    assert_eq!(buffer.left.samples[3], 1.0);
+    assert_eq!(buffer.right.samples[3], 0.0);
+
+    assert_eq!(buffer.left.samples[6], 0.0);
+    assert_eq!(buffer.right.samples[6], 0.5);
+
+    assert_eq!(buffer.left.samples[9], 0.25);
+    assert_eq!(buffer.right.samples[9], 0.0);
+}
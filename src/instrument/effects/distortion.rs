@@ -0,0 +1,359 @@
+use super::Effect;
+use crate::file_export::SoundBuffer;
+use crate::instrument::curve::Curve;
+
+use std::f64::consts::PI;
+
+// Taps of the oversampling filter per sample at the original sample rate
+const OVERSAMPLING_TAPS: usize = 32;
+
+/// Soft clipping using `tanh`. Small amplitudes stay almost unchanged, while
+/// large amplitudes are smoothly limited to `1.0`.
+#[derive(Clone, Copy, Debug)]
+pub struct SoftClip;
+
+/// Hard clipping. Every amplitude above `threshold` is cut off.
+#[derive(Clone, Copy, Debug)]
+pub struct HardClip {
+    pub threshold: f32,
+}
+
+/// Foldback distortion. Amplitudes above `threshold` are mirrored back
+/// (folded) at the threshold, which adds a lot of harmonics.
+#[derive(Clone, Copy, Debug)]
+pub struct Foldback {
+    pub threshold: f32,
+}
+
+/// A waveshaping distortion. Every sample is passed through a transfer
+/// function given by a `Curve`, where x is the input amplitude and y is the
+/// output amplitude.
+///
+/// The provided curves are [`SoftClip`], [`HardClip`] and [`Foldback`], but any
+/// `Curve` can be used, e.g. a [`crate::prelude::LinearCurve`].
+///
+/// Waveshaping adds harmonics, which may go beyond the Nyquist frequency and
+/// then alias back into audible frequencies. Oversampling renders the
+/// distortion at a multiple of the sample rate and filters the harmonics above
+/// the Nyquist frequency before converting back. The filter is a windowed
+/// sinc FIR, so the cost grows linearly with the length of the buffer.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use effects::distortion::*;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = SoundBuffer::from_parts(vec![0.5; 100], 100, settings);
+/// #
+/// let overdrive = Waveshaper::new(SoftClip)
+///     .set_drive(4.0)
+///     .set_oversampling(4);
+///
+/// overdrive.apply(&mut buffer);
+///
+/// let custom = Waveshaper::new(
+///     LinearCurve::new()
+///         .add_point(-1.0, -0.8)
+///         .add_point(-0.2, -0.5)
+///         .add_point(0.2, 0.5)
+///         .add_point(1.0, 0.8)
+/// );
+///
+/// custom.apply(&mut buffer);
+/// ```
+///
+/// It can also be used inside an instrument, either for every sample with
+/// `shape_sample()`, or for the whole tone inside `post_process`:
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use effects::distortion::*;
+/// use std::time::Duration;
+///
+/// #[derive(Clone, Copy)]
+/// struct FuzzySine;
+///
+/// impl Instrument for FuzzySine {
+///     type ConcreteValue = TET12ConcreteTone;
+///
+///     fn render_sample(&self, tone: Self::ConcreteValue, time: Duration) -> f32 {
+///         predefined::sine_wave(tone.to_frequency() as f64, time)
+///     }
+///
+///     fn post_process(&self, _tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+///         Waveshaper::new(Foldback { threshold: 0.5 })
+///             .set_drive(2.0)
+///             .apply(buffer);
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Waveshaper<C: Curve> {
+    curve: C,
+    drive: f32,
+    output_gain: f32,
+    mix: f32,
+    oversampling: usize,
+}
+
+/// Reduces the bit depth and the sample rate of a signal for a lo-fi sound.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use effects::distortion::Bitcrusher;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = SoundBuffer::from_parts(vec![0.5; 100], 100, settings);
+/// #
+/// Bitcrusher::new()
+///     .set_bits(6)
+///     .set_sample_rate(8000)
+///     .apply(&mut buffer);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Bitcrusher {
+    bits: Option<u32>,
+    sample_rate: Option<u32>,
+}
+
+impl Curve for SoftClip {
+    fn get(&self, x: f32) -> f32 {
+        x.tanh()
+    }
+}
+
+impl Curve for HardClip {
+    fn get(&self, x: f32) -> f32 {
+        x.clamp(-self.threshold, self.threshold)
+    }
+}
+
+impl Curve for Foldback {
+    fn get(&self, x: f32) -> f32 {
+        let t = self.threshold;
+
+        if t <= 0.0 {
+            return 0.0;
+        }
+
+        ((x - t).rem_euclid(4.0 * t) - 2.0 * t).abs() - t
+    }
+}
+
+impl<C: Curve> Waveshaper<C> {
+    /// Create a new waveshaper with the given transfer function.
+    pub fn new(curve: C) -> Self {
+        Self {
+            curve,
+            drive: 1.0,
+            output_gain: 1.0,
+            mix: 1.0,
+            oversampling: 1,
+        }
+    }
+
+    /// Set the gain that is applied before shaping. A higher drive distorts
+    /// the signal more.
+    pub fn set_drive(mut self, drive: f32) -> Self {
+        self.drive = drive;
+        self
+    }
+
+    /// Set the gain that is applied after shaping.
+    pub fn set_output_gain(mut self, output_gain: f32) -> Self {
+        self.output_gain = output_gain;
+        self
+    }
+
+    /// Set how much of the distorted signal is mixed with the original signal.
+    /// `1.0` is only the distorted signal, `0.0` is only the original signal.
+    pub fn set_mix(mut self, mix: f32) -> Self {
+        self.mix = mix.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the oversampling factor. A factor of `1` disables oversampling.
+    pub fn set_oversampling(mut self, factor: usize) -> Self {
+        self.oversampling = usize::max(factor, 1);
+        self
+    }
+
+    /// Shape a single sample. This does not apply oversampling.
+    pub fn shape_sample(&self, sample: f32) -> f32 {
+        let shaped = self.curve.get(sample * self.drive) * self.output_gain;
+        shaped * self.mix + sample * (1.0 - self.mix)
+    }
+}
+
+impl<C: Curve> Effect for Waveshaper<C> {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        if self.oversampling == 1 || buffer.samples.is_empty() {
+            for sample in buffer.samples.iter_mut() {
+                *sample = self.shape_sample(*sample);
+            }
+            return;
+        }
+
+        let kernel = lowpass_kernel(self.oversampling);
+        let mut oversampled = upsample(&buffer.samples, self.oversampling, &kernel);
+
+        for sample in oversampled.iter_mut() {
+            *sample = self.shape_sample(*sample);
+        }
+
+        buffer.samples = downsample(&oversampled, self.oversampling, &kernel);
+    }
+}
+
+impl Bitcrusher {
+    /// Create a new bitcrusher that doesn't change the signal yet.
+    pub fn new() -> Self {
+        Self {
+            bits: None,
+            sample_rate: None,
+        }
+    }
+
+    /// Set the bit depth the amplitude is quantized to.
+    pub fn set_bits(mut self, bits: u32) -> Self {
+        self.bits = Some(bits.clamp(1, 24));
+        self
+    }
+
+    /// Set the sample rate the signal is reduced to. Every sample is held
+    /// until the next sample of the reduced sample rate.
+    pub fn set_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    fn quantize(&self, sample: f32) -> f32 {
+        let Some(bits) = self.bits else {
+            return sample;
+        };
+
+        let levels = 2_f32.powi(bits as i32 - 1);
+        (sample * levels).round() / levels
+    }
+}
+
+impl Default for Bitcrusher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Bitcrusher {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let step = match self.sample_rate {
+            Some(sample_rate) => sample_rate as f32 / buffer.settings().sample_rate as f32,
+            None => 1.0,
+        };
+
+        let mut phase = 1.0;
+        let mut held = 0.0;
+
+        for sample in buffer.samples.iter_mut() {
+            if phase >= 1.0 {
+                phase -= 1.0;
+                held = self.quantize(*sample);
+            }
+            phase += step;
+
+            *sample = held;
+        }
+    }
+}
+
+/// A windowed sinc lowpass at the Nyquist frequency of the original sample
+/// rate, for a signal oversampled by `factor`. The kernel has an odd length
+/// and is centered at `kernel.len() / 2`, so it doesn't delay the signal.
+fn lowpass_kernel(factor: usize) -> Vec<f32> {
+    let center = OVERSAMPLING_TAPS / 2 * factor;
+    let len = 2 * center + 1;
+    let cutoff = 0.5 / factor as f64;
+
+    let kernel: Vec<f64> = (0..len).map(|i| {
+        let x = i as f64 - center as f64;
+        let sinc = if x == 0.0 {
+            2.0 * cutoff
+        } else {
+            (2.0 * PI * cutoff * x).sin() / (PI * x)
+        };
+
+        // Blackman window
+        let t = i as f64 / (len - 1) as f64;
+        let window = 0.42 - 0.5 * (2.0 * PI * t).cos() + 0.08 * (4.0 * PI * t).cos();
+
+        sinc * window
+    }).collect();
+
+    // Every phase gets the same gain, so a constant signal stays constant
+    // when upsampled
+    let mut kernel: Vec<f32> = kernel.iter().map(|h| *h as f32).collect();
+    for phase in 0..factor {
+        let sum: f32 = kernel.iter().skip(phase).step_by(factor).sum();
+        for h in kernel.iter_mut().skip(phase).step_by(factor) {
+            *h /= sum * factor as f32;
+        }
+    }
+
+    kernel
+}
+
+/// Insert `factor - 1` zeros between samples and filter them, which only
+/// needs every `factor`th tap per output sample (polyphase).
+fn upsample(samples: &[f32], factor: usize, kernel: &[f32]) -> Vec<f32> {
+    let center = kernel.len() / 2;
+
+    (0..samples.len() * factor).map(|i| {
+        // Taps that land on an original sample
+        let first_tap = (i + center) % factor;
+
+        (first_tap..kernel.len()).step_by(factor)
+            .map(|tap| {
+                let index = (i + center) as isize - tap as isize;
+                sample_at(samples, index / factor as isize) * kernel[tap]
+            })
+            .sum::<f32>() * factor as f32
+    }).collect()
+}
+
+/// Filter the signal and keep every `factor`th sample. Only the kept samples
+/// are calculated.
+fn downsample(samples: &[f32], factor: usize, kernel: &[f32]) -> Vec<f32> {
+    let center = kernel.len() / 2;
+
+    (0..samples.len() / factor).map(|i| {
+        let position = (i * factor + center) as isize;
+
+        kernel.iter().enumerate()
+            .map(|(tap, h)| sample_at(samples, position - tap as isize) * h)
+            .sum()
+    }).collect()
+}
+
+/// Outside the signal, it's continued by mirroring it at the first and last
+/// sample (point reflection). This keeps the value and the slope at the ends,
+/// so the filter doesn't ring like it would for a jump to zero.
+fn sample_at(samples: &[f32], index: isize) -> f32 {
+    let last = samples.len() as isize - 1;
+
+    let mirrored = |edge: isize, index: isize| {
+        2.0 * samples[edge as usize] - samples[index.clamp(0, last) as usize]
+    };
+
+    if index < 0 {
+        mirrored(0, -index)
+    } else if index > last {
+        mirrored(last, 2 * last - index)
+    } else {
+        samples[index as usize]
+    }
+}
+
+mod tests;
@@ -0,0 +1,156 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::CompositionSettings;
+use crate::instrument::curve::linear_curve::LinearCurve;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 44100,
+};
+
+fn assert_eq_f32(a: f32, b: f32, epsilon: f32) {
+    let delta = (a - b).abs();
+    if delta > epsilon {
+        panic!("assertion failed: {} != {}", a, b);
+    }
+}
+
+fn sine(frequency: f32, num_samples: usize, amplitude: f32) -> SoundBuffer {
+    let samples = (0..num_samples)
+        .map(|i| {
+            let t = i as f32 / SETTINGS.sample_rate as f32;
+            (t * frequency * 2.0 * std::f32::consts::PI).sin() * amplitude
+        })
+        .collect();
+
+    SoundBuffer::from_parts(samples, num_samples, SETTINGS)
+}
+
+#[test]
+fn shapes() {
+    assert_eq_f32(SoftClip.get(0.0), 0.0, 0.0001);
+    assert_eq_f32(SoftClip.get(100.0), 1.0, 0.0001);
+    assert_eq_f32(SoftClip.get(-100.0), -1.0, 0.0001);
+
+    let hard_clip = HardClip { threshold: 0.5 };
+    assert_eq_f32(hard_clip.get(0.3), 0.3, 0.0001);
+    assert_eq_f32(hard_clip.get(0.7), 0.5, 0.0001);
+    assert_eq_f32(hard_clip.get(-0.7), -0.5, 0.0001);
+
+    let foldback = Foldback { threshold: 0.5 };
+    assert_eq_f32(foldback.get(0.0), 0.0, 0.0001);
+    assert_eq_f32(foldback.get(0.3), 0.3, 0.0001);
+    assert_eq_f32(foldback.get(0.5), 0.5, 0.0001);
+    assert_eq_f32(foldback.get(0.7), 0.3, 0.0001);
+    assert_eq_f32(foldback.get(1.2), -0.2, 0.0001);
+    assert_eq_f32(foldback.get(-0.7), -0.3, 0.0001);
+}
+
+#[test]
+fn drive_and_mix() {
+    let shaper = Waveshaper::new(HardClip { threshold: 0.5 })
+        .set_drive(2.0)
+        .set_output_gain(2.0);
+
+    assert_eq_f32(shaper.shape_sample(0.1), 0.4, 0.0001);
+    assert_eq_f32(shaper.shape_sample(0.5), 1.0, 0.0001);
+
+    let shaper = shaper.set_mix(0.5);
+    assert_eq_f32(shaper.shape_sample(0.5), 0.75, 0.0001);
+}
+
+#[test]
+fn custom_curve() {
+    let curve = LinearCurve::new()
+        .add_point(-1.0, 1.0)
+        .add_point(0.0, 0.0)
+        .add_point(1.0, 1.0);
+
+    let mut buffer = SoundBuffer::from_parts(vec![-0.5, 0.25, 1.0], 3, SETTINGS);
+    Waveshaper::new(curve).apply(&mut buffer);
+
+    assert_eq!(buffer.samples, vec![0.5, 0.25, 1.0]);
+}
+
+#[test]
+fn oversampling_keeps_length() {
+    let mut buffer = sine(440.0, 1000, 0.5);
+    let original = buffer.clone();
+
+    // A linear transfer function must not change the signal
+    let identity = LinearCurve::new()
+        .add_point(-1.0, -1.0)
+        .add_point(1.0, 1.0);
+
+    Waveshaper::new(identity)
+        .set_oversampling(4)
+        .apply(&mut buffer);
+
+    assert_eq!(buffer.samples.len(), original.samples.len());
+    for i in 0..buffer.samples.len() {
+        assert_eq_f32(buffer.samples[i], original.samples[i], 0.001);
+    }
+}
+
+#[test]
+fn oversampling_reduces_aliasing() {
+    // Hard clipping a high frequency produces harmonics above Nyquist
+    let frequency = 5000.0;
+    let shaper = Waveshaper::new(HardClip { threshold: 0.2 });
+
+    let mut plain = sine(frequency, 4410, 1.0);
+    let mut oversampled = plain.clone();
+
+    shaper.apply(&mut plain);
+    shaper.set_oversampling(8).apply(&mut oversampled);
+
+    // Measure energy at the aliased frequency of the 5th harmonic:
+    // 25000 Hz mirrors to 19100 Hz
+    let energy = |buffer: &SoundBuffer| -> f32 {
+        let mut copy = buffer.clone();
+        crate::instrument::eq::filter_fft_whole_bandpass(&mut copy, 18900.0 .. 19300.0);
+        copy.samples.iter().map(|x| x * x).sum()
+    };
+
+    assert!(energy(&oversampled) < energy(&plain) * 0.5);
+}
+
+#[test]
+fn bitcrusher_bits() {
+    let mut buffer = SoundBuffer::from_parts(vec![0.1, 0.3, -0.6, 0.9], 4, SETTINGS);
+
+    Bitcrusher::new().set_bits(2).apply(&mut buffer);
+
+    assert_eq!(buffer.samples, vec![0.0, 0.5, -0.5, 1.0]);
+}
+
+#[test]
+fn bitcrusher_sample_rate() {
+    let samples: Vec<f32> = (0..8).map(|i| i as f32).collect();
+    let mut buffer = SoundBuffer::from_parts(samples, 8, SETTINGS);
+
+    Bitcrusher::new()
+        .set_sample_rate(SETTINGS.sample_rate / 4)
+        .apply(&mut buffer);
+
+    assert_eq!(buffer.samples, vec![0.0, 0.0, 0.0, 0.0, 4.0, 4.0, 4.0, 4.0]);
+}
+
+#[test]
+fn oversampling_doesnt_wrap_around() {
+    // Silence with a loud tone at the end
+    let mut buffer = SoundBuffer::from_parts(vec![0.0; 4000], 4000, SETTINGS);
+    let tone = sine(3000.0, 1000, 1.0);
+    buffer.samples[3000..].copy_from_slice(&tone.samples);
+
+    Waveshaper::new(HardClip { threshold: 0.3 })
+        .set_oversampling(4)
+        .apply(&mut buffer);
+
+    assert_eq!(buffer.samples.len(), 4000);
+
+    // Nothing of the tone may appear at the start of the buffer
+    for sample in &buffer.samples[..2900] {
+        assert_eq_f32(*sample, 0.0, 0.0001);
+    }
+}
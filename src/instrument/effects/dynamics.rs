@@ -0,0 +1,340 @@
+use super::{Effect, db_to_amplitude, amplitude_to_db};
+use crate::file_export::{SoundBuffer, StereoBuffer};
+
+use std::time::Duration;
+
+// Levels below this are treated as silence by the level detector
+const MIN_LEVEL_DB: f32 = -120.0;
+
+/// A compressor that reduces the amplitude of loud parts of a signal.
+///
+/// If the level of the signal goes above the `threshold`, the amount above the
+/// threshold is divided by `ratio`. The `knee` smoothes the transition around
+/// the threshold. How fast the compressor reacts is set with `attack` and
+/// `release`. The `makeup` gain is applied after compressing.
+///
+/// The level can also be detected from a different buffer than the one that
+/// is compressed (sidechain). This is commonly used to let the bass duck when
+/// the kick drum plays.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use std::time::Duration;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut bass = SoundBuffer::from_parts(vec![0.5; 100], 100, settings);
+/// # let kick = SoundBuffer::from_parts(vec![1.0; 100], 100, settings);
+/// #
+/// let compressor = Compressor::new()
+///     .set_threshold(-20.0)
+///     .set_ratio(8.0)
+///     .set_attack(Duration::from_millis(5))
+///     .set_release(Duration::from_millis(150));
+///
+/// // The bass is compressed whenever the kick is loud
+/// compressor.apply_sidechain(&mut bass, &kick);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Compressor {
+    threshold: f32,
+    ratio: f32,
+    attack: Duration,
+    release: Duration,
+    knee: f32,
+    makeup: f32,
+}
+
+/// A brickwall limiter for the master bus. The amplitude of the signal will
+/// never go above the `ceiling`.
+///
+/// The limiter looks ahead in the signal, so the gain is already reduced
+/// smoothly when a peak arrives. Prefer this over `SoundBuffer::normalize()`
+/// if only a few peaks are too loud, as the rest of the signal keeps its
+/// intensity.
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut composition = SoundBuffer::from_parts(vec![1.5; 100], 100, settings);
+/// #
+/// Limiter::new()
+///     .set_ceiling(-0.3)
+///     .apply(&mut composition);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Limiter {
+    ceiling: f32,
+    lookahead: Duration,
+    release: Duration,
+}
+
+impl Compressor {
+    /// Create a new compressor with moderate settings.
+    pub fn new() -> Self {
+        Self {
+            threshold: -18.0,
+            ratio: 4.0,
+            attack: Duration::from_millis(10),
+            release: Duration::from_millis(100),
+            knee: 6.0,
+            makeup: 0.0,
+        }
+    }
+
+    /// Set the level in dB above which the signal is compressed.
+    pub fn set_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the compression ratio. A ratio of e.g. `4.0` means that a signal
+    /// 4dB above the threshold comes out 1dB above the threshold. Values below
+    /// `1.0` are treated as `1.0` (no compression).
+    pub fn set_ratio(mut self, ratio: f32) -> Self {
+        self.ratio = ratio.max(1.0);
+        self
+    }
+
+    /// Set how long the compressor takes to react on a loud signal.
+    pub fn set_attack(mut self, attack: Duration) -> Self {
+        self.attack = attack;
+        self
+    }
+
+    /// Set how long the compressor takes to recover after a loud signal.
+    pub fn set_release(mut self, release: Duration) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Set the width of the knee in dB. A width of `0.0` is a hard knee.
+    pub fn set_knee(mut self, knee: f32) -> Self {
+        self.knee = knee.max(0.0);
+        self
+    }
+
+    /// Set the gain in dB that is applied after compressing.
+    pub fn set_makeup(mut self, makeup: f32) -> Self {
+        self.makeup = makeup;
+        self
+    }
+
+    /// Compress `buffer` using the level of `sidechain`. If the sidechain is
+    /// shorter than the buffer, it is treated as silent for the remaining
+    /// samples.
+    pub fn apply_sidechain(&self, buffer: &mut SoundBuffer, sidechain: &SoundBuffer) {
+        let gains = self.compute_gains(&sidechain.samples, buffer.samples.len(), buffer.settings().sample_rate);
+        Self::apply_gains(&mut buffer.samples, &gains);
+    }
+
+    /// The stereo variant of `apply_sidechain()`. Both channels are compressed
+    /// by the same amount.
+    pub fn apply_sidechain_stereo(&self, buffer: &mut StereoBuffer, sidechain: &SoundBuffer) {
+        self.apply_sidechain(&mut buffer.left, sidechain);
+        self.apply_sidechain(&mut buffer.right, sidechain);
+    }
+
+    /// Compute the gain reduction in dB for a given input level in dB.
+    fn gain_computer(&self, level: f32) -> f32 {
+        let overshoot = level - self.threshold;
+        let slope = 1.0 / self.ratio - 1.0;
+
+        if 2.0 * overshoot < -self.knee {
+            0.0
+        }
+        else if self.knee > 0.0 && 2.0 * overshoot.abs() <= self.knee {
+            let x = overshoot + self.knee / 2.0;
+            slope * x * x / (2.0 * self.knee)
+        }
+        else {
+            slope * overshoot
+        }
+    }
+
+    fn compute_gains(&self, detector: &[f32], num_samples: usize, sample_rate: u32) -> Vec<f32> {
+        let attack = smoothing_coefficient(self.attack, sample_rate);
+        let release = smoothing_coefficient(self.release, sample_rate);
+
+        let mut gains = Vec::with_capacity(num_samples);
+        let mut smoothed_reduction = 0.0;
+
+        for i in 0..num_samples {
+            let sample = detector.get(i).copied().unwrap_or(0.0);
+            let level = amplitude_to_db(sample.abs()).max(MIN_LEVEL_DB);
+            let reduction = self.gain_computer(level);
+
+            // The reduction is negative, a smaller value means more reduction
+            let coefficient = if reduction < smoothed_reduction { attack } else { release };
+            smoothed_reduction = coefficient * smoothed_reduction + (1.0 - coefficient) * reduction;
+
+            gains.push(db_to_amplitude(smoothed_reduction + self.makeup));
+        }
+
+        gains
+    }
+
+    fn apply_gains(samples: &mut [f32], gains: &[f32]) {
+        for (sample, gain) in samples.iter_mut().zip(gains) {
+            *sample *= gain;
+        }
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Compressor {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let gains = self.compute_gains(&buffer.samples, buffer.samples.len(), buffer.settings().sample_rate);
+        Self::apply_gains(&mut buffer.samples, &gains);
+    }
+
+    /// Both channels are compressed by the same amount, using the louder
+    /// channel for detecting the level.
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        let detector = peak_of_channels(buffer);
+        let sample_rate = buffer.settings().sample_rate;
+
+        let gains = self.compute_gains(&detector, detector.len(), sample_rate);
+        Self::apply_gains(&mut buffer.left.samples, &gains);
+        Self::apply_gains(&mut buffer.right.samples, &gains);
+    }
+}
+
+impl Limiter {
+    /// Create a new limiter with a ceiling of 0dB (amplitude `1.0`).
+    pub fn new() -> Self {
+        Self {
+            ceiling: 0.0,
+            lookahead: Duration::from_millis(5),
+            release: Duration::from_millis(50),
+        }
+    }
+
+    /// Set the maximum level of the signal in dB.
+    pub fn set_ceiling(mut self, ceiling: f32) -> Self {
+        self.ceiling = ceiling;
+        self
+    }
+
+    /// Set how far the limiter looks ahead. The gain is reduced over this
+    /// time before a peak arrives.
+    pub fn set_lookahead(mut self, lookahead: Duration) -> Self {
+        self.lookahead = lookahead;
+        self
+    }
+
+    /// Set how long the limiter takes to recover after a peak.
+    pub fn set_release(mut self, release: Duration) -> Self {
+        self.release = release;
+        self
+    }
+
+    fn compute_gains(&self, detector: &[f32], sample_rate: u32) -> Vec<f32> {
+        let ceiling = db_to_amplitude(self.ceiling);
+        let lookahead = usize::max(
+            (self.lookahead.as_secs_f64() * sample_rate as f64).round() as usize,
+            1,
+        );
+        let release = smoothing_coefficient(self.release, sample_rate);
+
+        let required_gains: Vec<f32> = detector.iter()
+            .map(|sample| {
+                let amplitude = sample.abs();
+                if amplitude > ceiling { ceiling / amplitude } else { 1.0 }
+            })
+            .collect();
+
+        // Every sample takes the lowest gain required within the lookahead,
+        // then the gain may only rise slowly again.
+        let mut held_gains = Vec::with_capacity(detector.len());
+        let mut previous = 1.0;
+
+        for i in 0..required_gains.len() {
+            let window_end = usize::min(i + lookahead, required_gains.len());
+            let window_min = required_gains[i..window_end]
+                .iter()
+                .fold(1.0_f32, |acc, x| acc.min(*x));
+
+            let released = release * previous + (1.0 - release) * window_min;
+            let gain = window_min.min(released);
+
+            held_gains.push(gain);
+            previous = gain;
+        }
+
+        // Average over the lookahead for a smooth gain reduction. Every value
+        // in the window already accounts for the peak, so the average never
+        // exceeds the required gain.
+        let mut gains = Vec::with_capacity(held_gains.len());
+        let mut sum = 0.0;
+
+        for i in 0..held_gains.len() {
+            sum += held_gains[i];
+            if i >= lookahead {
+                sum -= held_gains[i - lookahead];
+            }
+
+            let window_len = usize::min(i + 1, lookahead);
+            let average: f32 = sum / window_len as f32;
+            gains.push(average.min(held_gains[i]));
+        }
+
+        gains
+    }
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Limiter {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let gains = self.compute_gains(&buffer.samples, buffer.settings().sample_rate);
+        Compressor::apply_gains(&mut buffer.samples, &gains);
+    }
+
+    /// Both channels are limited by the same amount, so the stereo image stays
+    /// the same.
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        let detector = peak_of_channels(buffer);
+
+        let gains = self.compute_gains(&detector, buffer.settings().sample_rate);
+        Compressor::apply_gains(&mut buffer.left.samples, &gains);
+        Compressor::apply_gains(&mut buffer.right.samples, &gains);
+    }
+}
+
+/// Coefficient for a one-pole smoothing filter that reaches ~63% of its target
+/// after the given time.
+fn smoothing_coefficient(time: Duration, sample_rate: u32) -> f32 {
+    let samples = time.as_secs_f32() * sample_rate as f32;
+
+    if samples <= 0.0 {
+        return 0.0;
+    }
+
+    (-1.0 / samples).exp()
+}
+
+fn peak_of_channels(buffer: &StereoBuffer) -> Vec<f32> {
+    let num_samples = usize::max(buffer.left.samples.len(), buffer.right.samples.len());
+
+    (0..num_samples).map(|i| {
+        let left = buffer.left.samples.get(i).copied().unwrap_or(0.0);
+        let right = buffer.right.samples.get(i).copied().unwrap_or(0.0);
+        left.abs().max(right.abs())
+    }).collect()
+}
+
+mod tests;
@@ -0,0 +1,156 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::CompositionSettings;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 1000,
+};
+
+fn constant(amplitude: f32, num_samples: usize) -> SoundBuffer {
+    SoundBuffer::from_parts(vec![amplitude; num_samples], num_samples, SETTINGS)
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0, |acc, x| acc.max(x.abs()))
+}
+
+// Tests for Compressor
+
+#[test]
+fn gain_computer_hard_knee() {
+    let compressor = Compressor::new()
+        .set_threshold(-20.0)
+        .set_ratio(4.0)
+        .set_knee(0.0);
+
+    assert_eq!(compressor.gain_computer(-30.0), 0.0);
+    assert_eq!(compressor.gain_computer(-20.0), 0.0);
+    assert_eq!(compressor.gain_computer(-12.0), -6.0);
+    assert_eq!(compressor.gain_computer(0.0), -15.0);
+}
+
+#[test]
+fn gain_computer_soft_knee() {
+    let compressor = Compressor::new()
+        .set_threshold(-20.0)
+        .set_ratio(4.0)
+        .set_knee(10.0);
+
+    // Outside of the knee it behaves like a hard knee
+    assert_eq!(compressor.gain_computer(-26.0), 0.0);
+    assert_eq!(compressor.gain_computer(-12.0), -6.0);
+
+    // Inside of the knee there is some reduction already
+    let reduction = compressor.gain_computer(-20.0);
+    assert!(reduction < 0.0 && reduction > -1.0);
+}
+
+#[test]
+fn compress_constant_signal() {
+    // 0.1 is -20dB; 10dB above a threshold of -30dB
+    let mut buffer = constant(0.1, 2000);
+
+    Compressor::new()
+        .set_threshold(-30.0)
+        .set_ratio(2.0)
+        .set_knee(0.0)
+        .set_attack(Duration::from_millis(10))
+        .apply(&mut buffer);
+
+    // After settling the signal is 5dB above the threshold
+    let expected = db_to_amplitude(-25.0);
+    assert!((buffer.samples[1999] - expected).abs() < 0.001);
+
+    // The attack takes some time
+    assert!(buffer.samples[1] > buffer.samples[1999]);
+}
+
+#[test]
+fn makeup_gain() {
+    let mut buffer = constant(0.01, 100);
+
+    Compressor::new()
+        .set_threshold(0.0)
+        .set_makeup(20.0)
+        .apply(&mut buffer);
+
+    assert!((buffer.samples[50] - 0.1).abs() < 0.0001);
+}
+
+#[test]
+fn sidechain_ducking() {
+    let mut bass = constant(0.5, 1000);
+
+    let mut kick_samples = vec![0.0; 1000];
+    for sample in kick_samples[0..100].iter_mut() {
+        *sample = 1.0;
+    }
+    let kick = SoundBuffer::from_parts(kick_samples, 1000, SETTINGS);
+
+    Compressor::new()
+        .set_threshold(-20.0)
+        .set_ratio(10.0)
+        .set_attack(Duration::ZERO)
+        .set_release(Duration::from_millis(10))
+        .apply_sidechain(&mut bass, &kick);
+
+    // Ducked while the kick plays, back to normal afterwards
+    assert!(bass.samples[50] < 0.1);
+    assert!((bass.samples[999] - 0.5).abs() < 0.001);
+}
+
+// Tests for Limiter
+
+#[test]
+fn limiter_brickwall() {
+    let samples: Vec<f32> = (0..1000)
+        .map(|i| (i as f32 * 0.37).sin() * (1.0 + (i % 100) as f32 / 20.0))
+        .collect();
+    let mut buffer = SoundBuffer::from_parts(samples, 1000, SETTINGS);
+
+    Limiter::new().set_ceiling(-1.0).apply(&mut buffer);
+
+    assert!(peak(&buffer.samples) <= db_to_amplitude(-1.0) + 0.00001);
+}
+
+#[test]
+fn limiter_quiet_signal_unchanged() {
+    let mut buffer = constant(0.5, 100);
+
+    Limiter::new().apply(&mut buffer);
+
+    assert!(buffer.samples.iter().all(|x| *x == 0.5));
+}
+
+#[test]
+fn limiter_lookahead() {
+    let mut samples = vec![0.5; 100];
+    samples[50] = 2.0;
+    let mut buffer = SoundBuffer::from_parts(samples, 100, SETTINGS);
+
+    Limiter::new()
+        .set_lookahead(Duration::from_millis(10))
+        .apply(&mut buffer);
+
+    assert!(buffer.samples[50] <= 1.00001);
+
+    // The gain is reduced before the peak arrives
+    assert!(buffer.samples[45] < 0.5);
+    assert_eq!(buffer.samples[30], 0.5);
+}
+
+#[test]
+fn limiter_stereo_linked() {
+    let mut buffer = StereoBuffer {
+        left: constant(2.0, 100),
+        right: constant(0.5, 100),
+    };
+
+    Limiter::new().apply_stereo(&mut buffer);
+
+    for i in 0..100 {
+        assert!(buffer.left.samples[i] <= 1.00001);
+        assert!((buffer.left.samples[i] / buffer.right.samples[i] - 4.0).abs() < 0.001);
+    }
+}
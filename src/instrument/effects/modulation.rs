@@ -0,0 +1,390 @@
+use super::Effect;
+use crate::file_export::{SoundBuffer, StereoBuffer};
+
+use std::f32::consts::PI;
+use std::time::Duration;
+
+// The tail is rendered until the feedback has decayed by 60dB
+const TAIL_DECAY: f32 = 0.001;
+const MAX_FEEDBACK: f32 = 0.95;
+
+/// A delay line whose delay time is modulated by a low frequency oscillator
+/// (LFO). This is the base of chorus and flanger effects, construct these with
+/// `ModulatedDelay::chorus()` or `ModulatedDelay::flanger()` and adjust the
+/// parameters afterwards.
+///
+/// - A **chorus** uses a longer delay (around 20ms) and no feedback. It sounds
+///   like several instruments playing the same notes, which makes thin sounds
+///   (e.g. a raw saw wave) wider and less static.
+///
+/// - A **flanger** uses a very short delay (a few ms) with feedback. This
+///   results in a sweeping comb filter.
+///
+/// The LFO of the right channel is shifted by the stereo spread when applied
+/// on a `StereoBuffer`.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use std::time::Duration;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = StereoBuffer::from_mono(
+/// #     SoundBuffer::from_parts(vec![0.5; 100], 100, settings)
+/// # );
+/// #
+/// let chorus = ModulatedDelay::chorus()
+///     .set_rate(0.5)
+///     .set_depth(Duration::from_millis(4))
+///     .set_voices(3)
+///     .set_spread(1.0);
+///
+/// chorus.apply_stereo(&mut buffer);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ModulatedDelay {
+    delay: Duration,
+    depth: Duration,
+    rate: f32,
+    feedback: f32,
+    voices: usize,
+    spread: f32,
+    mix: f32,
+}
+
+/// A phaser effect. The signal passes a chain of allpass filters whose
+/// frequency is swept by a low frequency oscillator (LFO). Mixed with the
+/// original signal, this creates moving notches in the spectrum.
+///
+/// The LFO of the right channel is shifted by the stereo spread when applied
+/// on a `StereoBuffer`.
+///
+/// ```
+/// use synth_music::prelude::*;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = SoundBuffer::from_parts(vec![0.5; 100], 100, settings);
+/// #
+/// let phaser = Phaser::new()
+///     .set_stages(6)
+///     .set_rate(0.3)
+///     .set_frequency_range(200.0 .. 4000.0)
+///     .set_feedback(0.5);
+///
+/// phaser.apply(&mut buffer);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Phaser {
+    stages: usize,
+    rate: f32,
+    frequency_range: std::ops::Range<f32>,
+    feedback: f32,
+    spread: f32,
+    mix: f32,
+}
+
+impl ModulatedDelay {
+    /// Create a chorus with a delay of 20ms.
+    pub fn chorus() -> Self {
+        Self {
+            delay: Duration::from_millis(20),
+            depth: Duration::from_millis(5),
+            rate: 0.8,
+            feedback: 0.0,
+            voices: 2,
+            spread: 0.5,
+            mix: 0.5,
+        }
+    }
+
+    /// Create a flanger with a delay of 2.5ms and strong feedback.
+    pub fn flanger() -> Self {
+        Self {
+            delay: Duration::from_micros(2500),
+            depth: Duration::from_millis(2),
+            rate: 0.25,
+            feedback: 0.7,
+            voices: 1,
+            spread: 0.25,
+            mix: 0.5,
+        }
+    }
+
+    /// Set the delay around which the delay time is modulated.
+    pub fn set_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set how much the delay time is modulated by the LFO.
+    pub fn set_depth(mut self, depth: Duration) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Set the frequency of the LFO in Hz.
+    pub fn set_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set how much of the delayed signal is fed back into the delay line.
+    /// The value is limited to the range from `-0.95` to `0.95`.
+    pub fn set_feedback(mut self, feedback: f32) -> Self {
+        self.feedback = feedback.clamp(-MAX_FEEDBACK, MAX_FEEDBACK);
+        self
+    }
+
+    /// Set the amount of delayed voices. Every voice has its own LFO, which
+    /// are evenly spread across the LFO cycle.
+    pub fn set_voices(mut self, voices: usize) -> Self {
+        self.voices = usize::max(voices, 1);
+        self
+    }
+
+    /// Set how much the LFO of the right channel is shifted in the range from
+    /// `0.0` (no shift) to `1.0` (half a cycle).
+    pub fn set_spread(mut self, spread: f32) -> Self {
+        self.spread = spread.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how much of the delayed signal is mixed with the original signal.
+    /// `1.0` is only the delayed signal, `0.0` is only the original signal.
+    pub fn set_mix(mut self, mix: f32) -> Self {
+        self.mix = mix.clamp(0.0, 1.0);
+        self
+    }
+
+    fn tail_samples(&self, sample_rate: u32) -> usize {
+        let max_delay = duration_to_samples(self.delay + self.depth, sample_rate).ceil() as usize + 1;
+
+        let repetitions = if self.feedback != 0.0 {
+            (TAIL_DECAY.ln() / self.feedback.abs().ln()).ceil() as usize
+        } else {
+            0
+        };
+
+        max_delay * (repetitions + 1)
+    }
+
+    fn process(&self, input: &[f32], sample_rate: u32, phase_offset: f32) -> Vec<f32> {
+        let delay = duration_to_samples(self.delay, sample_rate);
+        let depth = duration_to_samples(self.depth, sample_rate);
+
+        let line_len = (delay + depth).ceil() as usize + 2;
+        let mut lines = vec![DelayLine::new(line_len); self.voices];
+
+        let output_len = input.len() + self.tail_samples(sample_rate);
+        let mut output = Vec::with_capacity(output_len);
+
+        for i in 0..output_len {
+            let input_sample = input.get(i).copied().unwrap_or(0.0);
+            let time = i as f32 / sample_rate as f32;
+
+            let mut wet = 0.0;
+
+            for (voice, line) in lines.iter_mut().enumerate() {
+                let voice_phase = voice as f32 / self.voices as f32 * 2.0 * PI;
+                let lfo = lfo(self.rate, time, phase_offset + voice_phase);
+
+                let delayed = line.read(delay + depth * lfo);
+                line.write(input_sample + delayed * self.feedback);
+
+                wet += delayed;
+            }
+
+            wet /= self.voices as f32;
+            output.push(input_sample * (1.0 - self.mix) + wet * self.mix);
+        }
+
+        output
+    }
+}
+
+impl Effect for ModulatedDelay {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let sample_rate = buffer.settings().sample_rate;
+        buffer.samples = self.process(&buffer.samples, sample_rate, 0.0);
+    }
+
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        let sample_rate = buffer.settings().sample_rate;
+        let phase_offset = self.spread * PI;
+
+        buffer.left.samples = self.process(&buffer.left.samples, sample_rate, 0.0);
+        buffer.right.samples = self.process(&buffer.right.samples, sample_rate, phase_offset);
+    }
+}
+
+impl Phaser {
+    /// Create a new phaser with four allpass stages.
+    pub fn new() -> Self {
+        Self {
+            stages: 4,
+            rate: 0.5,
+            frequency_range: 300.0 .. 3000.0,
+            feedback: 0.0,
+            spread: 0.5,
+            mix: 0.5,
+        }
+    }
+
+    /// Set the amount of allpass filters. Every two stages add one notch.
+    pub fn set_stages(mut self, stages: usize) -> Self {
+        self.stages = usize::max(stages, 1);
+        self
+    }
+
+    /// Set the frequency of the LFO in Hz.
+    pub fn set_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set the range of frequencies the allpass filters are swept across. The
+    /// sweep is scaled logarithmically.
+    pub fn set_frequency_range(mut self, frequency_range: std::ops::Range<f32>) -> Self {
+        self.frequency_range = frequency_range;
+        self
+    }
+
+    /// Set how much of the filtered signal is fed back into the filter chain.
+    /// The value is limited to the range from `-0.95` to `0.95`.
+    pub fn set_feedback(mut self, feedback: f32) -> Self {
+        self.feedback = feedback.clamp(-MAX_FEEDBACK, MAX_FEEDBACK);
+        self
+    }
+
+    /// Set how much the LFO of the right channel is shifted in the range from
+    /// `0.0` (no shift) to `1.0` (half a cycle).
+    pub fn set_spread(mut self, spread: f32) -> Self {
+        self.spread = spread.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how much of the filtered signal is mixed with the original signal.
+    pub fn set_mix(mut self, mix: f32) -> Self {
+        self.mix = mix.clamp(0.0, 1.0);
+        self
+    }
+
+    fn process(&self, input: &[f32], sample_rate: u32, phase_offset: f32) -> Vec<f32> {
+        let mut filters = vec![AllpassFilter::default(); self.stages];
+        let mut last_output = 0.0;
+
+        let min_frequency = self.frequency_range.start.max(1.0);
+        let max_frequency = self.frequency_range.end.max(min_frequency);
+        let nyquist = sample_rate as f32 / 2.0;
+
+        let mut output = Vec::with_capacity(input.len());
+
+        for (i, input_sample) in input.iter().enumerate() {
+            let time = i as f32 / sample_rate as f32;
+
+            let lfo = (lfo(self.rate, time, phase_offset) + 1.0) / 2.0;
+            let frequency = (min_frequency * (max_frequency / min_frequency).powf(lfo)).min(nyquist * 0.99);
+
+            let tan = (PI * frequency / sample_rate as f32).tan();
+            let coefficient = (tan - 1.0) / (tan + 1.0);
+
+            let mut sample = input_sample + last_output * self.feedback;
+            for filter in filters.iter_mut() {
+                sample = filter.process(sample, coefficient);
+            }
+            last_output = sample;
+
+            output.push(input_sample * (1.0 - self.mix) + sample * self.mix);
+        }
+
+        output
+    }
+}
+
+impl Default for Phaser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Phaser {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let sample_rate = buffer.settings().sample_rate;
+        buffer.samples = self.process(&buffer.samples, sample_rate, 0.0);
+    }
+
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        let sample_rate = buffer.settings().sample_rate;
+        let phase_offset = self.spread * PI;
+
+        buffer.left.samples = self.process(&buffer.left.samples, sample_rate, 0.0);
+        buffer.right.samples = self.process(&buffer.right.samples, sample_rate, phase_offset);
+    }
+}
+
+/// A sine LFO in the range from `-1.0` to `1.0`.
+fn lfo(rate: f32, time: f32, phase: f32) -> f32 {
+    (2.0 * PI * rate * time + phase).sin()
+}
+
+fn duration_to_samples(duration: Duration, sample_rate: u32) -> f32 {
+    duration.as_secs_f32() * sample_rate as f32
+}
+
+/// A circular delay line that can be read at fractional positions.
+#[derive(Clone)]
+struct DelayLine {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl DelayLine {
+    fn new(size: usize) -> Self {
+        Self {
+            buffer: vec![0.0; size],
+            index: 0,
+        }
+    }
+
+    /// Read the sample that was written `delay` samples ago, interpolating
+    /// linearly between samples. The delay is limited to the size of the line.
+    fn read(&self, delay: f32) -> f32 {
+        let len = self.buffer.len();
+        let delay = delay.clamp(1.0, (len - 1) as f32);
+
+        let whole = delay.floor() as usize;
+        let t = delay - whole as f32;
+
+        let a = self.buffer[(self.index + len - whole) % len];
+        let b = self.buffer[(self.index + len - whole - 1) % len];
+
+        a + (b - a) * t
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.index] = sample;
+        self.index = (self.index + 1) % self.buffer.len();
+    }
+}
+
+/// A first-order allpass filter.
+#[derive(Clone, Copy, Default)]
+struct AllpassFilter {
+    last_input: f32,
+    last_output: f32,
+}
+
+impl AllpassFilter {
+    fn process(&mut self, input: f32, coefficient: f32) -> f32 {
+        let output = coefficient * input + self.last_input - coefficient * self.last_output;
+
+        self.last_input = input;
+        self.last_output = output;
+        output
+    }
+}
+
+mod tests;
@@ -0,0 +1,120 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::CompositionSettings;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 1000,
+};
+
+fn assert_eq_f32(a: f32, b: f32, epsilon: f32) {
+    let delta = (a - b).abs();
+    if delta > epsilon {
+        panic!("assertion failed: {} != {}", a, b);
+    }
+}
+
+fn impulse(num_samples: usize) -> SoundBuffer {
+    let mut samples = vec![0.0; num_samples];
+    samples[0] = 1.0;
+
+    SoundBuffer::from_parts(samples, num_samples, SETTINGS)
+}
+
+fn noise(num_samples: usize) -> SoundBuffer {
+    let samples = (0..num_samples)
+        .map(|i| (((i * 7919) % 1000) as f32 / 500.0) - 1.0)
+        .collect();
+
+    SoundBuffer::from_parts(samples, num_samples, SETTINGS)
+}
+
+#[test]
+fn delay_line_fractional() {
+    let mut line = DelayLine::new(8);
+
+    for sample in [1.0, 2.0, 3.0, 4.0] {
+        line.write(sample);
+    }
+
+    assert_eq_f32(line.read(1.0), 4.0, 0.0001);
+    assert_eq_f32(line.read(2.0), 3.0, 0.0001);
+    assert_eq_f32(line.read(1.5), 3.5, 0.0001);
+    assert_eq_f32(line.read(3.25), 1.75, 0.0001);
+}
+
+#[test]
+fn fixed_delay_without_modulation() {
+    let mut buffer = impulse(20);
+
+    ModulatedDelay::chorus()
+        .set_delay(Duration::from_millis(5))
+        .set_depth(Duration::ZERO)
+        .set_voices(1)
+        .set_mix(1.0)
+        .apply(&mut buffer);
+
+    assert_eq_f32(buffer.samples[0], 0.0, 0.0001);
+    assert_eq_f32(buffer.samples[5], 1.0, 0.0001);
+}
+
+#[test]
+fn flanger_feedback_tail() {
+    let mut buffer = impulse(20);
+
+    ModulatedDelay::flanger()
+        .set_delay(Duration::from_millis(4))
+        .set_depth(Duration::ZERO)
+        .set_feedback(0.5)
+        .set_mix(1.0)
+        .apply(&mut buffer);
+
+    assert_eq_f32(buffer.samples[4], 1.0, 0.0001);
+    assert_eq_f32(buffer.samples[8], 0.5, 0.0001);
+    assert_eq_f32(buffer.samples[12], 0.25, 0.0001);
+
+    assert_eq!(buffer.active_samples(), 20);
+    assert!(buffer.samples.len() > 20);
+}
+
+#[test]
+fn dry_mix_unchanged() {
+    let original = noise(200);
+
+    let mut chorus = original.clone();
+    ModulatedDelay::chorus().set_mix(0.0).apply(&mut chorus);
+
+    let mut phaser = original.clone();
+    Phaser::new().set_mix(0.0).apply(&mut phaser);
+
+    for i in 0..original.samples.len() {
+        assert_eq_f32(chorus.samples[i], original.samples[i], 0.0001);
+        assert_eq_f32(phaser.samples[i], original.samples[i], 0.0001);
+    }
+}
+
+#[test]
+fn allpass_keeps_energy() {
+    let mut filter = AllpassFilter::default();
+
+    let input = noise(1000).samples;
+    let output: Vec<f32> = input.iter().map(|x| filter.process(*x, -0.5)).collect();
+
+    let energy_in: f32 = input.iter().map(|x| x * x).sum();
+    let energy_out: f32 = output.iter().map(|x| x * x).sum();
+
+    assert_eq_f32(energy_in, energy_out, energy_in * 0.05);
+}
+
+#[test]
+fn stereo_spread() {
+    let mut buffer = StereoBuffer::from_mono(noise(500));
+    Phaser::new().set_spread(1.0).apply_stereo(&mut buffer);
+
+    assert_ne!(buffer.left.samples, buffer.right.samples);
+
+    let mut buffer = StereoBuffer::from_mono(noise(500));
+    ModulatedDelay::chorus().set_spread(0.0).apply_stereo(&mut buffer);
+
+    assert_eq!(buffer.left.samples, buffer.right.samples);
+}
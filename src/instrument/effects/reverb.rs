@@ -0,0 +1,283 @@
+use super::Effect;
+use crate::file_export::{SoundBuffer, StereoBuffer};
+
+use std::time::Duration;
+
+// Tunings of the original Freeverb, given in samples at 44100 Hz
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+const STEREO_SPREAD: usize = 23;
+const TUNING_SAMPLE_RATE: f32 = 44100.0;
+
+const FIXED_GAIN: f32 = 0.015;
+const SCALE_WET: f32 = 3.0;
+const SCALE_DAMPING: f32 = 0.4;
+const SCALE_ROOM: f32 = 0.28;
+const OFFSET_ROOM: f32 = 0.7;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+// The tail is rendered until the reverb has decayed by 60dB
+const TAIL_DECAY: f32 = 0.001;
+
+/// An algorithmic reverb based on the Freeverb design. It consists of eight
+/// parallel comb filters with damping, followed by four allpass filters in
+/// series, for each channel.
+///
+/// The reverb tail is not truncated at the end of the buffer. The buffer is
+/// extended after its active samples, so the tail will be mixed into the
+/// following section when appending.
+///
+/// ```
+/// use synth_music::prelude::*;
+/// use std::time::Duration;
+///
+/// # let settings = CompositionSettings {
+/// #     sample_rate: 44100,
+/// # };
+/// # let mut buffer = SoundBuffer::from_parts(vec![1.0; 100], 100, settings);
+/// #
+/// let reverb = Reverb::new()
+///     .set_room_size(0.8)
+///     .set_damping(0.3)
+///     .set_pre_delay(Duration::from_millis(20))
+///     .set_wet(0.25);
+///
+/// reverb.apply(&mut buffer);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Reverb {
+    room_size: f32,
+    damping: f32,
+    pre_delay: Duration,
+    width: f32,
+    wet: f32,
+    dry: f32,
+}
+
+impl Reverb {
+    /// Create a new reverb with a medium sized room.
+    pub fn new() -> Self {
+        Self {
+            room_size: 0.5,
+            damping: 0.5,
+            pre_delay: Duration::ZERO,
+            width: 1.0,
+            wet: 0.3,
+            dry: 1.0,
+        }
+    }
+
+    /// Set the size of the room in the range from `0.0` to `1.0`. A larger room
+    /// will have a longer reverb tail.
+    pub fn set_room_size(mut self, room_size: f32) -> Self {
+        self.room_size = room_size.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how much high frequencies are absorbed in the range from `0.0` to
+    /// `1.0`. A higher value makes the reverb sound darker.
+    pub fn set_damping(mut self, damping: f32) -> Self {
+        self.damping = damping.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the time between the dry signal and the start of the reverb.
+    pub fn set_pre_delay(mut self, pre_delay: Duration) -> Self {
+        self.pre_delay = pre_delay;
+        self
+    }
+
+    /// Set the stereo width of the reverb in the range from `0.0` to `1.0`.
+    /// This only has an effect when applied on a `StereoBuffer`.
+    pub fn set_width(mut self, width: f32) -> Self {
+        self.width = width.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the amplitude of the reverberated signal.
+    pub fn set_wet(mut self, wet: f32) -> Self {
+        self.wet = wet;
+        self
+    }
+
+    /// Set the amplitude of the original signal.
+    pub fn set_dry(mut self, dry: f32) -> Self {
+        self.dry = dry;
+        self
+    }
+
+    /// Compute the amount of samples the reverb keeps ringing after the input
+    /// has become silent.
+    pub fn tail_samples(&self, sample_rate: u32) -> usize {
+        let feedback = self.feedback();
+        let longest_comb = Self::scale_tuning(COMB_TUNINGS[7] + STEREO_SPREAD, sample_rate);
+
+        let comb_tail = if feedback > 0.0 {
+            (longest_comb as f32 * TAIL_DECAY.ln() / feedback.ln()).ceil() as usize
+        } else {
+            longest_comb
+        };
+
+        let allpass_tail: usize = ALLPASS_TUNINGS.iter()
+            .map(|tuning| Self::scale_tuning(*tuning + STEREO_SPREAD, sample_rate))
+            .sum();
+
+        comb_tail + allpass_tail + self.pre_delay_samples(sample_rate)
+    }
+
+    fn feedback(&self) -> f32 {
+        self.room_size * SCALE_ROOM + OFFSET_ROOM
+    }
+
+    fn pre_delay_samples(&self, sample_rate: u32) -> usize {
+        (self.pre_delay.as_secs_f64() * sample_rate as f64).round() as usize
+    }
+
+    fn scale_tuning(tuning: usize, sample_rate: u32) -> usize {
+        let factor = sample_rate as f32 / TUNING_SAMPLE_RATE;
+        usize::max((tuning as f32 * factor).round() as usize, 1)
+    }
+
+    fn process_channel(&self, input: &[f32], spread: usize, sample_rate: u32) -> Vec<f32> {
+        let feedback = self.feedback();
+        let damping = self.damping * SCALE_DAMPING;
+
+        let mut combs: Vec<Comb> = COMB_TUNINGS.iter()
+            .map(|tuning| Comb::new(Self::scale_tuning(tuning + spread, sample_rate), feedback, damping))
+            .collect();
+
+        let mut allpasses: Vec<Allpass> = ALLPASS_TUNINGS.iter()
+            .map(|tuning| Allpass::new(Self::scale_tuning(tuning + spread, sample_rate)))
+            .collect();
+
+        let pre_delay = self.pre_delay_samples(sample_rate);
+        let total_samples = input.len() + self.tail_samples(sample_rate);
+
+        let mut output = Vec::with_capacity(total_samples);
+
+        for i in 0..total_samples {
+            let input_sample = match i.checked_sub(pre_delay) {
+                Some(index) => input.get(index).copied().unwrap_or(0.0),
+                None => 0.0,
+            } * FIXED_GAIN;
+
+            let mut sample = 0.0;
+            for comb in combs.iter_mut() {
+                sample += comb.process(input_sample);
+            }
+            for allpass in allpasses.iter_mut() {
+                sample = allpass.process(sample);
+            }
+
+            output.push(sample);
+        }
+
+        output
+    }
+
+    fn mix_channel(&self, dry: &[f32], wet_main: &[f32], wet_cross: &[f32]) -> Vec<f32> {
+        let wet = self.wet * SCALE_WET;
+        let wet_main_gain = wet * (self.width / 2.0 + 0.5);
+        let wet_cross_gain = wet * ((1.0 - self.width) / 2.0);
+
+        let mut output = Vec::with_capacity(wet_main.len());
+
+        for i in 0..wet_main.len() {
+            let dry_sample = dry.get(i).copied().unwrap_or(0.0);
+            let sample =
+                dry_sample * self.dry
+                + wet_main[i] * wet_main_gain
+                + wet_cross[i] * wet_cross_gain;
+
+            output.push(sample);
+        }
+
+        output
+    }
+}
+
+impl Default for Reverb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Reverb {
+    fn apply(&self, buffer: &mut SoundBuffer) {
+        let mut stereo = StereoBuffer::from_mono(buffer.clone());
+        self.apply_stereo(&mut stereo);
+        *buffer = stereo.to_mono();
+    }
+
+    fn apply_stereo(&self, buffer: &mut StereoBuffer) {
+        let sample_rate = buffer.settings().sample_rate;
+
+        let wet_left = self.process_channel(&buffer.left.samples, 0, sample_rate);
+        let wet_right = self.process_channel(&buffer.right.samples, STEREO_SPREAD, sample_rate);
+
+        let left = self.mix_channel(&buffer.left.samples, &wet_left, &wet_right);
+        let right = self.mix_channel(&buffer.right.samples, &wet_right, &wet_left);
+
+        buffer.left.samples = left;
+        buffer.right.samples = right;
+    }
+}
+
+/// A feedback comb filter with a lowpass filter inside the feedback loop.
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    feedback: f32,
+    damping: f32,
+    filter_store: f32,
+}
+
+impl Comb {
+    fn new(size: usize, feedback: f32, damping: f32) -> Self {
+        Self {
+            buffer: vec![0.0; size],
+            index: 0,
+            feedback,
+            damping,
+            filter_store: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.buffer[self.index];
+
+        self.filter_store = output * (1.0 - self.damping) + self.filter_store * self.damping;
+        self.buffer[self.index] = input + self.filter_store * self.feedback;
+
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+/// A Schroeder allpass filter, used for diffusing the echoes of the comb
+/// filters.
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn new(size: usize) -> Self {
+        Self {
+            buffer: vec![0.0; size],
+            index: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let buffered = self.buffer[self.index];
+        let output = buffered - input;
+
+        self.buffer[self.index] = input + buffered * ALLPASS_FEEDBACK;
+
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+mod tests;
@@ -0,0 +1,106 @@
+#![cfg(test)]
+
+use super::*;
+use crate::file_export::CompositionSettings;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 44100,
+};
+
+fn impulse(num_samples: usize, settings: CompositionSettings) -> SoundBuffer {
+    let mut samples = vec![0.0; num_samples];
+    samples[0] = 1.0;
+
+    SoundBuffer::from_parts(samples, num_samples, settings)
+}
+
+#[test]
+fn tail_extends_buffer() {
+    let mut buffer = impulse(1000, SETTINGS);
+    let reverb = Reverb::new();
+
+    reverb.apply(&mut buffer);
+
+    let expected_len = 1000 + reverb.tail_samples(SETTINGS.sample_rate);
+
+    assert_eq!(buffer.active_samples(), 1000);
+    assert_eq!(buffer.samples.len(), expected_len);
+
+    // Reverb must be audible after the active samples
+    let tail_energy: f32 = buffer.samples[1000..].iter().map(|x| x * x).sum();
+    assert!(tail_energy > 0.0);
+}
+
+#[test]
+fn tail_decays() {
+    let mut buffer = impulse(100, SETTINGS);
+    Reverb::new().set_room_size(1.0).apply(&mut buffer);
+
+    let last = buffer.samples[buffer.samples.len() - 100..]
+        .iter()
+        .fold(0.0_f32, |acc, x| acc.max(x.abs()));
+
+    assert!(last < 0.001);
+}
+
+#[test]
+fn larger_room_longer_tail() {
+    let small = Reverb::new().set_room_size(0.1);
+    let large = Reverb::new().set_room_size(0.9);
+
+    assert!(large.tail_samples(44100) > small.tail_samples(44100));
+}
+
+#[test]
+fn dry_only() {
+    let mut buffer = impulse(10, SETTINGS);
+    Reverb::new().set_wet(0.0).set_dry(1.0).apply(&mut buffer);
+
+    assert_eq!(buffer.samples[0], 1.0);
+    assert!(buffer.samples[1..].iter().all(|x| *x == 0.0));
+}
+
+#[test]
+fn pre_delay() {
+    let mut buffer = impulse(10, SETTINGS);
+    let reverb = Reverb::new()
+        .set_dry(0.0)
+        .set_pre_delay(Duration::from_millis(100));
+
+    reverb.apply(&mut buffer);
+
+    // The earliest reflection is after the pre delay and the shortest comb
+    let silent_samples = 4410 + COMB_TUNINGS[0];
+    assert!(buffer.samples[..silent_samples].iter().all(|x| *x == 0.0));
+    assert!(buffer.samples[silent_samples..].iter().any(|x| *x != 0.0));
+}
+
+#[test]
+fn sample_rate_independent() {
+    let reverb = Reverb::new();
+
+    let tail_44100 = reverb.tail_samples(44100) as f32 / 44100.0;
+    let tail_96000 = reverb.tail_samples(96000) as f32 / 96000.0;
+
+    assert!((tail_44100 - tail_96000).abs() < 0.01);
+}
+
+#[test]
+fn stereo_width() {
+    let settings = SETTINGS;
+    let mut narrow = StereoBuffer::from_mono(impulse(100, settings));
+    let mut wide = StereoBuffer::from_mono(impulse(100, settings));
+
+    Reverb::new().set_width(0.0).apply_stereo(&mut narrow);
+    Reverb::new().set_width(1.0).apply_stereo(&mut wide);
+
+    let difference = |buffer: &StereoBuffer| -> f32 {
+        buffer.left.samples.iter()
+            .zip(buffer.right.samples.iter())
+            .map(|(l, r)| (l - r).abs())
+            .sum()
+    };
+
+    assert!(difference(&narrow) < 0.0001);
+    assert!(difference(&wide) > 0.0001);
+}
@@ -2,6 +2,8 @@ pub mod tet12;
 
 use tet12::TET12ConcreteTone;
 use super::Instrument;
+use crate::composer::parameter::PITCH_BEND;
+use crate::file_export::{SoundBuffer, Tone};
 
 use std::time::Duration;
 
@@ -53,25 +55,33 @@ pub fn saw_wave_phase(frequency: f64, time: Duration, phase: f64) -> f32 {
     return (x % 2.0) - 1.0;
 }
 
-/// An implementor for `Instrument` that uses a raw sine wave.
+/// An implementor for `Instrument` that uses a raw sine wave. The pitch is
+/// bent by the `PITCH_BEND` parameter of the tones.
 #[derive(Clone, Copy)]
 pub struct SineGenerator;
 
-/// An implementor for `Instrument` that uses a raw triangle wave.
+/// An implementor for `Instrument` that uses a raw triangle wave. The pitch is
+/// bent by the `PITCH_BEND` parameter of the tones.
 #[derive(Clone, Copy)]
 pub struct TriangleGenerator;
 
-/// An implementor for `Instrument` that uses a raw square wave.
+/// An implementor for `Instrument` that uses a raw square wave. The pitch is
+/// bent by the `PITCH_BEND` parameter of the tones.
 #[derive(Clone, Copy)]
 pub struct SquareGenerator;
 
-/// An implementor for `Instrument` that uses a raw saw wave.
+/// An implementor for `Instrument` that uses a raw saw wave. The pitch is
+/// bent by the `PITCH_BEND` parameter of the tones.
 #[derive(Clone, Copy)]
 pub struct SawGenerator;
 
 impl Instrument for SineGenerator {
     type ConcreteValue = TET12ConcreteTone;
 
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        render_bent_wave(self, sine_wave, tones, buffer);
+    }
+
     fn render_sample(&self, tone: Self::ConcreteValue, time: Duration) -> f32 {
         let frequency = tone.to_frequency() as f64;
         return sine_wave(frequency, time);
@@ -81,6 +91,10 @@ impl Instrument for SineGenerator {
 impl Instrument for TriangleGenerator {
     type ConcreteValue = TET12ConcreteTone;
 
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        render_bent_wave(self, triangle_wave, tones, buffer);
+    }
+
     fn render_sample(&self, tone: Self::ConcreteValue, time: Duration) -> f32 {
         let frequency = tone.to_frequency() as f64;
         return triangle_wave(frequency, time);
@@ -90,6 +104,10 @@ impl Instrument for TriangleGenerator {
 impl Instrument for SquareGenerator {
     type ConcreteValue = TET12ConcreteTone;
 
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        render_bent_wave(self, square_wave, tones, buffer);
+    }
+
     fn render_sample(&self, tone: Self::ConcreteValue, time: Duration) -> f32 {
         let frequency = tone.to_frequency() as f64;
         return square_wave(frequency, time);
@@ -98,9 +116,73 @@ impl Instrument for SquareGenerator {
 
 impl Instrument for SawGenerator {
     type ConcreteValue = TET12ConcreteTone;
-    
+
+    fn render(&self, tones: &Tone<Self::ConcreteValue>, buffer: &mut SoundBuffer) {
+        render_bent_wave(self, saw_wave, tones, buffer);
+    }
+
     fn render_sample(&self, tone: Self::ConcreteValue, time: Duration) -> f32 {
         let frequency = tone.to_frequency() as f64;
         return saw_wave(frequency, time);
     }
 }
+
+/// Render the tones like the default `Instrument::render()`, but bend the
+/// frequency of the wave by the `PITCH_BEND` parameter of the tones. The phase
+/// of a bent wave is accumulated sample by sample, so the wave stays
+/// continuous while the pitch changes.
+fn render_bent_wave<I>(
+    instrument: &I,
+    wave: fn(f64, Duration) -> f32,
+    tones: &Tone<TET12ConcreteTone>,
+    buffer: &mut SoundBuffer,
+)
+where
+    I: Instrument<ConcreteValue = TET12ConcreteTone>,
+{
+    let num_samples = instrument.get_num_samples(buffer, tones);
+    let sample_rate = buffer.settings().sample_rate as f64;
+    let bent = tones.parameters.get(PITCH_BEND).is_some();
+
+    let mut tone_buffers = Vec::with_capacity(tones.concrete_values.len() + 1);
+    tone_buffers.push(SoundBuffer::from_parts(
+        vec![0.0; num_samples],
+        buffer.active_samples(),
+        buffer.settings(),
+    ));
+
+    for tone in &tones.concrete_values {
+        if !bent {
+            let mut tone_buffer = SoundBuffer::from_parts(
+                Vec::with_capacity(num_samples),
+                buffer.active_samples(),
+                buffer.settings(),
+            );
+            instrument.render_tone_buffer(*tone, &mut tone_buffer, num_samples);
+            tone_buffers.push(tone_buffer);
+            continue;
+        }
+
+        let frequency = tone.to_frequency() as f64;
+        let mut cycles = 0.0;
+
+        let samples = (0..num_samples)
+            .map(|i| {
+                // A wave of 1 Hz at the time of `cycles` seconds is at the
+                // accumulated phase
+                let sample = wave(1.0, Duration::from_secs_f64(cycles));
+
+                let bend = tones.parameter(PITCH_BEND, buffer.time_from_index(i)) as f64;
+                cycles += frequency * 2_f64.powf(bend / 12.0) / sample_rate;
+                sample
+            })
+            .collect();
+
+        tone_buffers.push(SoundBuffer::from_parts(samples, buffer.active_samples(), buffer.settings()));
+    }
+
+    instrument.mix_tone_samples(tone_buffers, buffer);
+
+    instrument.apply_intensity(tones, buffer);
+    instrument.post_process(tones, buffer);
+}
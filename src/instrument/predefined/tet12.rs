@@ -1,5 +1,13 @@
 use crate::composer::{MusicKey, KeyType, KeyTonic};
 use crate::composer::ScaledValue;
+use crate::instrument::combinators::Transpose;
+
+use std::str::FromStr;
+
+pub mod absolute;
+pub mod chord;
+
+pub use chord::{Chord, Voicing};
 
 /// An abstract representation of a note value dependent on a `MusicKey`.
 /// Construct these with the functions `first(x)`, `second(x)`, ...
@@ -8,11 +16,29 @@ use crate::composer::ScaledValue;
 /// is the octave. If the scale e.g. is "C Major", the note `first(4)` is C4.
 /// 
 /// These can also be sharpened or flattened afterwards.
+/// 
+/// Absolute notes that don't depend on the key can be constructed with the
+/// functions in `absolute`, or parsed from their name:
+/// 
+/// ```
+/// use synth_music::prelude::*;
+/// use tet12::*;
+/// 
+/// let a = absolute::c(4).sharp();
+/// let b: TET12ScaledTone = "C#4".parse().unwrap();
+/// assert_eq!(a, b);
+/// 
+/// // Both are C#4, no matter the key
+/// let d_major = MusicKey { tonic: KeyTonic::D, key_type: KeyType::Major };
+/// assert_eq!(a.to_concrete_value(d_major), TET12ConcreteTone(-8));
+/// ```
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct TET12ScaledTone {
     index: u8,
     octave: i32,
     offset: i32,
+    // The index refers to the note names C to B instead of the scale
+    absolute: bool,
 }
 
 /// A concrete representation of a note value without a music key. This is
@@ -31,6 +57,30 @@ impl ScaledValue for TET12ScaledTone {
     }
 }
 
+impl FromStr for TET12ScaledTone {
+    type Err = &'static str;
+
+    /// Parse an absolute note from its name, see `absolute::parse()`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        absolute::parse(name)
+    }
+}
+
+impl Transpose for TET12ScaledTone {
+    fn transpose(self, semitones: i32) -> Self {
+        Self {
+            offset: self.offset + semitones,
+            ..self
+        }
+    }
+}
+
+impl Transpose for TET12ConcreteTone {
+    fn transpose(self, semitones: i32) -> Self {
+        Self(self.0 + semitones)
+    }
+}
+
 impl TET12ConcreteTone {
     /// Convert the tone into a frequency with unit Hz.
     pub fn to_frequency(self) -> f32 {
@@ -58,9 +108,17 @@ impl TET12ScaledTone {
     }
 
     fn get_concrete_value(self, key: MusicKey) -> i32 {
+        let octave_offset = (self.octave - 4) * 12;
+
+        if self.absolute {
+            let distance_c4_from_a4 = Self::distance_from_a4(KeyTonic::C);
+            let distance_from_c = Self::get_distance_from_tonic(KeyType::Major, self.index);
+
+            return distance_from_c + distance_c4_from_a4 + octave_offset + self.offset;
+        }
+
         let distance_from_tonic = Self::get_distance_from_tonic(key.key_type, self.index);
         let distance_tonic_from_a4 = Self::distance_from_a4(key.tonic);
-        let octave_offset = (self.octave - 4) * 12;
 
         return 
             distance_from_tonic
@@ -98,38 +156,29 @@ impl TET12ScaledTone {
     }
 
     fn get_distance_from_tonic(key_type: KeyType, index: u8) -> i32 {
-        match key_type {
-            KeyType::Major => Self::get_distance_major(index),
-            KeyType::Minor => Self::get_distance_minor(index),
-        }
+        key_type.distance(index.into())
     }
+}
 
-    fn get_distance_major(index: u8) -> i32 {
-        match index {
-            0 => 0,
-            1 => 2,
-            2 => 4,
-            3 => 5,
-            4 => 7,
-            5 => 9,
-            6 => 11,
-
-            _ => panic!("Invalid scaled value index"),
-        }
+/// Construct a note by its degree in the scale, where `1` is the first note.
+/// This is needed for scales with more than seven degrees; degrees beyond the
+/// scale continue in the next octave.
+/// 
+/// The pitch of a degree depends on the key, so scaled tones aren't ordered by
+/// themselves. Compare their `to_concrete_value()` in the key instead, like
+/// `Arpeggiator` does.
+/// 
+/// Panics if the degree is `0`.
+pub fn degree(degree: u8, octave: i32) -> TET12ScaledTone {
+    if degree == 0 {
+        panic!("Degrees start at 1");
     }
 
-    fn get_distance_minor(index: u8) -> i32 {
-        match index {
-            0 => 0,
-            1 => 2,
-            2 => 3,
-            3 => 5,
-            4 => 7,
-            5 => 8,
-            6 => 10,
-
-            _ => panic!("Invalid scaled value index"),
-        }
+    TET12ScaledTone {
+        index: degree - 1,
+        octave,
+        offset: 0,
+        absolute: false,
     }
 }
 
@@ -139,6 +188,7 @@ pub fn first(octave: i32) -> TET12ScaledTone {
         index: 0,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
@@ -148,6 +198,7 @@ pub fn second(octave: i32) -> TET12ScaledTone {
         index: 1,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
@@ -157,6 +208,7 @@ pub fn third(octave: i32) -> TET12ScaledTone {
         index: 2,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
@@ -166,6 +218,7 @@ pub fn fourth(octave: i32) -> TET12ScaledTone {
         index: 3,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
@@ -175,6 +228,7 @@ pub fn fifth(octave: i32) -> TET12ScaledTone {
         index: 4,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
@@ -184,6 +238,7 @@ pub fn sixth(octave: i32) -> TET12ScaledTone {
         index: 5,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
@@ -193,6 +248,7 @@ pub fn seventh(octave: i32) -> TET12ScaledTone {
         index: 6,
         octave,
         offset: 0,
+        absolute: false,
     }
 }
 
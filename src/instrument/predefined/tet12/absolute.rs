@@ -0,0 +1,168 @@
+//! Absolute notes that don't depend on the `MusicKey` of a section. They can be
+//! placed on the same track as notes constructed with scale degrees.
+//! 
+//! The octave starts at C, so `b(3)` is one semitone below `c(4)`, which is
+//! the middle C.
+//! 
+//! ```
+//! use synth_music::prelude::*;
+//! use tet12::*;
+//! use length::*;
+//! 
+//! let mut track = UnboundTrack::new(predefined::SineGenerator);
+//! 
+//! track.note(QUARTER, first(4));
+//! track.note(QUARTER, absolute::f(4).sharp());
+//! track.note(QUARTER, absolute::b_flat(3));
+//! track.note(QUARTER, absolute::note("F##5"));
+//! ```
+
+use super::TET12ScaledTone;
+
+/// Parse an absolute note from its name, which is made of a letter from A to
+/// G, any amount of accidentals (`#` for sharp, `b` for flat), and the
+/// octave. E.g. "C#4", "Bb2", "F##5" or "Eb-1".
+pub fn parse(name: &str) -> Result<TET12ScaledTone, &'static str> {
+    let mut chars = name.chars();
+
+    let index = match chars.next().map(|letter| letter.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 1,
+        Some('E') => 2,
+        Some('F') => 3,
+        Some('G') => 4,
+        Some('A') => 5,
+        Some('B') => 6,
+        Some(_) => return Err("Invalid note letter"),
+        None => return Err("Empty note name"),
+    };
+
+    let rest = chars.as_str();
+    let octave_start = rest.find(|c: char| c.is_ascii_digit() || c == '-')
+        .ok_or("Missing octave")?;
+    let (accidentals, octave) = rest.split_at(octave_start);
+
+    let mut offset = 0;
+    for accidental in accidentals.chars() {
+        match accidental {
+            '#' => offset += 1,
+            'b' => offset -= 1,
+            _ => return Err("Invalid accidental"),
+        }
+    }
+
+    let octave = octave.parse().map_err(|_| "Invalid octave")?;
+
+    Ok(TET12ScaledTone {
+        index,
+        octave,
+        offset,
+        absolute: true,
+    })
+}
+
+/// Construct an absolute note from its name. See `parse()` for the format.
+/// 
+/// Panics if the name is invalid.
+pub fn note(name: &str) -> TET12ScaledTone {
+    match parse(name) {
+        Ok(tone) => tone,
+        Err(error) => panic!("Invalid note name \"{}\": {}", name, error),
+    }
+}
+
+fn natural(index: u8, octave: i32) -> TET12ScaledTone {
+    TET12ScaledTone {
+        index,
+        octave,
+        offset: 0,
+        absolute: true,
+    }
+}
+
+/// Construct a C
+pub fn c(octave: i32) -> TET12ScaledTone {
+    natural(0, octave)
+}
+
+/// Construct a C#
+pub fn c_sharp(octave: i32) -> TET12ScaledTone {
+    c(octave).sharp()
+}
+
+/// Construct a Db
+pub fn d_flat(octave: i32) -> TET12ScaledTone {
+    d(octave).flat()
+}
+
+/// Construct a D
+pub fn d(octave: i32) -> TET12ScaledTone {
+    natural(1, octave)
+}
+
+/// Construct a D#
+pub fn d_sharp(octave: i32) -> TET12ScaledTone {
+    d(octave).sharp()
+}
+
+/// Construct an Eb
+pub fn e_flat(octave: i32) -> TET12ScaledTone {
+    e(octave).flat()
+}
+
+/// Construct an E
+pub fn e(octave: i32) -> TET12ScaledTone {
+    natural(2, octave)
+}
+
+/// Construct an F
+pub fn f(octave: i32) -> TET12ScaledTone {
+    natural(3, octave)
+}
+
+/// Construct an F#
+pub fn f_sharp(octave: i32) -> TET12ScaledTone {
+    f(octave).sharp()
+}
+
+/// Construct a Gb
+pub fn g_flat(octave: i32) -> TET12ScaledTone {
+    g(octave).flat()
+}
+
+/// Construct a G
+pub fn g(octave: i32) -> TET12ScaledTone {
+    natural(4, octave)
+}
+
+/// Construct a G#
+pub fn g_sharp(octave: i32) -> TET12ScaledTone {
+    g(octave).sharp()
+}
+
+/// Construct an Ab
+pub fn a_flat(octave: i32) -> TET12ScaledTone {
+    a(octave).flat()
+}
+
+/// Construct an A
+pub fn a(octave: i32) -> TET12ScaledTone {
+    natural(5, octave)
+}
+
+/// Construct an A#
+pub fn a_sharp(octave: i32) -> TET12ScaledTone {
+    a(octave).sharp()
+}
+
+/// Construct a Bb
+pub fn b_flat(octave: i32) -> TET12ScaledTone {
+    b(octave).flat()
+}
+
+/// Construct a B
+pub fn b(octave: i32) -> TET12ScaledTone {
+    natural(6, octave)
+}
+
+mod tests;
@@ -0,0 +1,50 @@
+#![cfg(test)]
+
+use super::*;
+use crate::composer::{MusicKey, KeyTonic, KeyType, ScaledValue};
+use crate::composer::music_key::C_MAJOR;
+use super::super::TET12ConcreteTone;
+
+fn concrete(tone: TET12ScaledTone, key: MusicKey) -> i32 {
+    tone.to_concrete_value(key).0
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse("C4"), Ok(c(4)));
+    assert_eq!(parse("C#4"), Ok(c_sharp(4)));
+    assert_eq!(parse("Bb2"), Ok(b_flat(2)));
+    assert_eq!(parse("F##5"), Ok(f(5).sharp().sharp()));
+    assert_eq!(parse("ebb3"), Ok(e(3).flat().flat()));
+    assert_eq!(parse("A-1"), Ok(a(-1)));
+
+    assert!(parse("").is_err());
+    assert!(parse("H4").is_err());
+    assert!(parse("C").is_err());
+    assert!(parse("Cx4").is_err());
+    assert!(parse("C4b").is_err());
+}
+
+#[test]
+fn test_concrete_values() {
+    assert_eq!(concrete(a(4), C_MAJOR), 0);
+    assert_eq!(concrete(c(4), C_MAJOR), -9);
+    assert_eq!(concrete(b(3), C_MAJOR), -10);
+    assert_eq!(concrete(c_sharp(4), C_MAJOR), -8);
+    assert_eq!(concrete(note("B#3"), C_MAJOR), -9);
+    assert_eq!(concrete(note("Cb4"), C_MAJOR), -10);
+    assert_eq!(concrete(note("G5"), C_MAJOR), 10);
+}
+
+#[test]
+fn test_ignores_key() {
+    let keys = [
+        C_MAJOR,
+        MusicKey { tonic: KeyTonic::Fsharp, key_type: KeyType::Minor },
+        MusicKey { tonic: KeyTonic::Bflat, key_type: KeyType::Major },
+    ];
+
+    for key in keys {
+        assert_eq!(note("Eb4").to_concrete_value(key), TET12ConcreteTone(-6));
+    }
+}
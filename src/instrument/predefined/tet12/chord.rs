@@ -0,0 +1,309 @@
+//! Chords built from Roman numerals or chord symbols.
+//! 
+//! Roman numerals are relative to the key of the section, just like notes
+//! constructed with scale degrees. Uppercase numerals are major chords and
+//! lowercase numerals are minor chords. Chord symbols are absolute and ignore
+//! the key.
+//! 
+//! ```
+//! use synth_music::prelude::*;
+//! use tet12::*;
+//! use length::*;
+//! 
+//! let mut track = MeasureTrack::new(predefined::SineGenerator, TimeSignature::new(4, 4));
+//! 
+//! Chord::roman("I", 4).unwrap().place(&mut track, QUARTER);
+//! Chord::roman("vi7", 3).unwrap().place(&mut track, QUARTER);
+//! Chord::roman("V7/V", 3).unwrap().set_inversion(1).place(&mut track, QUARTER);
+//! Chord::roman("V7", 3).unwrap().set_voicing(Voicing::Drop2).place(&mut track, QUARTER);
+//! track.measure().unwrap();
+//! 
+//! Chord::symbol("Cmaj7", 3).unwrap().place(&mut track, HALF);
+//! Chord::symbol("Gsus4/D", 3).unwrap().place(&mut track, HALF);
+//! track.measure().unwrap();
+//! ```
+//! 
+//! Supported qualities are: major (no suffix), `m`, `dim`/`o`, `aug`/`+`,
+//! `sus2`, `sus4`, `6`, `m6`, `7`, `maj7`, `m7`, `mmaj7`, `m7b5`/`ø`, `dim7`/
+//! `o7`, `7sus4`, `add9`, `9`, `maj9` and `m9`. Roman numerals use the same
+//! suffixes, where the `m` is implied by a lowercase numeral (e.g. `ii7`).
+
+use super::{TET12ScaledTone, absolute, degree};
+use crate::composer::{MusicTrack, Note, Length, KeyType};
+use crate::composer::music_key::C_MAJOR;
+use crate::composer::ScaledValue;
+use crate::instrument::Instrument;
+use crate::instrument::combinators::Transpose;
+
+use super::TET12ConcreteTone;
+
+/// A chord that can be placed on a track. Construct it with `Chord::roman()`,
+/// `Chord::symbol()`, or from a root and intervals with `Chord::new()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Chord {
+    root: TET12ScaledTone,
+    intervals: Vec<i32>,
+    bass: Option<TET12ScaledTone>,
+    inversion: usize,
+    voicing: Voicing,
+}
+
+/// How the tones of a chord are spread over the octaves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Voicing {
+    /// All tones within one octave.
+    #[default]
+    Close,
+    /// Every second tone from the bottom is moved up an octave, e.g. C G E for
+    /// a C major triad.
+    Open,
+    /// The second highest tone is moved down an octave.
+    Drop2,
+}
+
+impl Chord {
+    /// Create a chord from the root and the intervals of all chord tones
+    /// above the root in semitones (including `0` for the root itself).
+    pub fn new(root: TET12ScaledTone, intervals: &[i32]) -> Self {
+        let mut intervals = intervals.to_vec();
+        intervals.sort();
+
+        Self {
+            root,
+            intervals,
+            bass: None,
+            inversion: 0,
+            voicing: Voicing::Close,
+        }
+    }
+
+    /// Parse a Roman numeral relative to the key, e.g. "I", "vi7", "bVII",
+    /// "viio" or "V7/V" (secondary dominant). The root is placed in the given
+    /// octave.
+    pub fn roman(numeral: &str, octave: i32) -> Result<Self, &'static str> {
+        let (numeral, target) = match numeral.split_once('/') {
+            Some((numeral, target)) => (numeral, Some(target)),
+            None => (numeral, None),
+        };
+
+        let (root, quality) = parse_numeral(numeral, octave)?;
+        let intervals = quality_intervals(&quality).ok_or("Invalid chord quality")?;
+
+        // A secondary chord is built on a degree of the target chord's major
+        // scale, and kept in the given octave like the other numerals
+        let root = match target {
+            Some(target) => {
+                let target_root = Self::roman(target, octave)?.root;
+                let distance = KeyType::Major.distance(root.index as usize);
+                let above_tonic = KeyType::Major.distance(target_root.index as usize) + distance;
+                let octave_shift = if above_tonic >= 12 { -12 } else { 0 };
+
+                target_root.transpose(distance + root.offset + octave_shift)
+            }
+            None => root,
+        };
+
+        Ok(Self::new(root, intervals))
+    }
+
+    /// Parse a chord symbol, e.g. "C", "F#m7b5", "Bbmaj7" or "Gsus4/D". The
+    /// root is placed in the given octave, and the bass note of a slash chord
+    /// below the chord.
+    pub fn symbol(symbol: &str, octave: i32) -> Result<Self, &'static str> {
+        let (symbol, bass) = match symbol.split_once('/') {
+            Some((symbol, bass)) => (symbol, Some(bass)),
+            None => (symbol, None),
+        };
+
+        let root_length = note_name_length(symbol);
+        if root_length == 0 {
+            return Err("Invalid chord root");
+        }
+
+        let (root, quality) = symbol.split_at(root_length);
+        let root = absolute::parse(&format!("{}{}", root, octave))?;
+        let intervals = quality_intervals(quality).ok_or("Invalid chord quality")?;
+
+        let bass = match bass {
+            Some(bass) if note_name_length(bass) == bass.len() && !bass.is_empty() => {
+                Some(absolute::parse(&format!("{}{}", bass, octave))?)
+            }
+            Some(_) => return Err("Invalid bass note"),
+            None => None,
+        };
+
+        Ok(Self {
+            bass,
+            ..Self::new(root, intervals)
+        })
+    }
+
+    /// Invert the chord; the lowest tone is moved up an octave for every
+    /// inversion.
+    pub fn set_inversion(mut self, inversion: usize) -> Self {
+        self.inversion = inversion;
+        self
+    }
+
+    /// Set how the tones are spread over the octaves. The voicing is applied
+    /// after the inversion.
+    pub fn set_voicing(mut self, voicing: Voicing) -> Self {
+        self.voicing = voicing;
+        self
+    }
+
+    /// Set a bass note, which is placed below the lowest tone of the chord.
+    /// This only works for chords with an absolute root, like those parsed
+    /// from symbols.
+    pub fn set_bass(mut self, bass: TET12ScaledTone) -> Self {
+        self.bass = Some(bass);
+        self
+    }
+
+    /// Get the tones of the chord from the lowest to the highest.
+    pub fn tones(&self) -> Vec<TET12ScaledTone> {
+        let mut intervals = self.intervals.clone();
+
+        for _ in 0..self.inversion {
+            if let Some(lowest) = intervals.first().copied() {
+                intervals.remove(0);
+                intervals.push(lowest + 12);
+            }
+        }
+
+        match self.voicing {
+            Voicing::Close => (),
+            Voicing::Open => {
+                for interval in intervals.iter_mut().skip(1).step_by(2) {
+                    *interval += 12;
+                }
+            }
+            Voicing::Drop2 => {
+                if intervals.len() >= 2 {
+                    let index = intervals.len() - 2;
+                    intervals[index] -= 12;
+                }
+            }
+        }
+
+        intervals.sort();
+
+        let mut tones: Vec<TET12ScaledTone> = intervals.iter()
+            .map(|interval| self.root.transpose(*interval))
+            .collect();
+
+        if let Some(bass) = self.bass {
+            let lowest = tones.first().map_or(i32::MAX, |tone| concrete(*tone));
+
+            let mut bass = bass;
+            while concrete(bass) >= lowest {
+                bass = bass.transpose(-12);
+            }
+
+            tones.insert(0, bass);
+        }
+
+        tones
+    }
+
+    /// Place the chord on a track.
+    pub fn place<'a, T, U>(&self, track: &'a mut T, length: Length) -> &'a mut Note<TET12ScaledTone>
+    where
+        T: MusicTrack<TET12ScaledTone, U>,
+        U: Instrument<ConcreteValue = TET12ConcreteTone>,
+    {
+        track.notes(length, self.tones())
+    }
+}
+
+impl From<Chord> for Vec<TET12ScaledTone> {
+    fn from(chord: Chord) -> Self {
+        chord.tones()
+    }
+}
+
+// The value of an absolute tone, only used for comparing pitches
+fn concrete(tone: TET12ScaledTone) -> i32 {
+    tone.to_concrete_value(C_MAJOR).0
+}
+
+/// The length of the note name (letter and accidentals) at the start.
+fn note_name_length(name: &str) -> usize {
+    let mut chars = name.char_indices();
+
+    match chars.next() {
+        Some((_, letter)) if ('A'..='G').contains(&letter) => (),
+        _ => return 0,
+    }
+
+    chars.find(|(_, c)| *c != '#' && *c != 'b')
+        .map_or(name.len(), |(index, _)| index)
+}
+
+/// Parse a Roman numeral with an optional accidental before it. Returns the
+/// root and the quality suffix, with an `m` added for lowercase numerals.
+fn parse_numeral(numeral: &str, octave: i32) -> Result<(TET12ScaledTone, String), &'static str> {
+    const NUMERALS: [(&str, u8); 7] = [
+        ("VII", 7), ("VI", 6), ("IV", 4), ("V", 5), ("III", 3), ("II", 2), ("I", 1),
+    ];
+
+    let accidentals = numeral.find(|c: char| c != '#' && c != 'b').unwrap_or(numeral.len());
+    let (accidentals, rest) = numeral.split_at(accidentals);
+
+    let offset: i32 = accidentals.chars()
+        .map(|c| if c == '#' { 1 } else { -1 })
+        .sum();
+
+    for (name, number) in NUMERALS {
+        let uppercase = rest.starts_with(name);
+        let lowercase = rest.starts_with(&name.to_lowercase());
+
+        if !uppercase && !lowercase {
+            continue;
+        }
+
+        let suffix = &rest[name.len()..];
+        let quality = match suffix.chars().next() {
+            _ if uppercase => suffix.to_string(),
+            // Diminished chords are always minor
+            Some('o' | '°' | 'ø') => suffix.to_string(),
+            _ => format!("m{}", suffix),
+        };
+
+        let root = degree(number, octave).transpose(offset);
+        return Ok((root, quality));
+    }
+
+    Err("Invalid Roman numeral")
+}
+
+/// The intervals of a chord quality in semitones above the root.
+fn quality_intervals(quality: &str) -> Option<&'static [i32]> {
+    let intervals: &[i32] = match quality {
+        "" | "maj" | "M"            => &[0, 4, 7],
+        "m" | "min" | "-"           => &[0, 3, 7],
+        "dim" | "o" | "°"           => &[0, 3, 6],
+        "aug" | "+"                 => &[0, 4, 8],
+        "sus2"                      => &[0, 2, 7],
+        "sus4" | "sus"              => &[0, 5, 7],
+        "6"                         => &[0, 4, 7, 9],
+        "m6"                        => &[0, 3, 7, 9],
+        "7"                         => &[0, 4, 7, 10],
+        "maj7" | "M7"               => &[0, 4, 7, 11],
+        "m7" | "min7" | "-7"        => &[0, 3, 7, 10],
+        "mmaj7" | "mM7"             => &[0, 3, 7, 11],
+        "m7b5" | "ø" | "ø7"         => &[0, 3, 6, 10],
+        "dim7" | "o7" | "°7"        => &[0, 3, 6, 9],
+        "+7" | "aug7"               => &[0, 4, 8, 10],
+        "7sus4"                     => &[0, 5, 7, 10],
+        "add9"                      => &[0, 4, 7, 14],
+        "9"                         => &[0, 4, 7, 10, 14],
+        "maj9"                      => &[0, 4, 7, 11, 14],
+        "m9"                        => &[0, 3, 7, 10, 14],
+        _ => return None,
+    };
+
+    Some(intervals)
+}
+
+mod tests;
@@ -0,0 +1,158 @@
+#![cfg(test)]
+
+use super::*;
+use crate::composer::{MusicKey, KeyTonic, KeyType, UnboundTrack, SectionInfo};
+use crate::composer::length::QUARTER;
+use crate::file_export::CompositionSettings;
+use crate::instrument::predefined::SineGenerator;
+
+const SETTINGS: CompositionSettings = CompositionSettings {
+    sample_rate: 8000,
+};
+
+fn values(chord: &Chord, key: MusicKey) -> Vec<i32> {
+    chord.tones().into_iter()
+        .map(|tone| tone.to_concrete_value(key).0)
+        .collect()
+}
+
+// C4 relative to A4
+const C4: i32 = -9;
+
+#[test]
+fn test_roman_triads() {
+    assert_eq!(values(&Chord::roman("I", 4).unwrap(), C_MAJOR), vec![C4, C4 + 4, C4 + 7]);
+    assert_eq!(values(&Chord::roman("ii", 4).unwrap(), C_MAJOR), vec![C4 + 2, C4 + 5, C4 + 9]);
+    assert_eq!(values(&Chord::roman("viio", 4).unwrap(), C_MAJOR), vec![C4 + 11, C4 + 14, C4 + 17]);
+    assert_eq!(values(&Chord::roman("bVII", 4).unwrap(), C_MAJOR), vec![C4 + 10, C4 + 14, C4 + 17]);
+    assert_eq!(values(&Chord::roman("III+", 4).unwrap(), C_MAJOR), vec![C4 + 4, C4 + 8, C4 + 12]);
+}
+
+#[test]
+fn test_roman_sevenths() {
+    assert_eq!(values(&Chord::roman("vi7", 3).unwrap(), C_MAJOR), vec![C4 - 3, C4, C4 + 4, C4 + 7]);
+    assert_eq!(values(&Chord::roman("V7", 3).unwrap(), C_MAJOR), vec![C4 - 5, C4 - 1, C4 + 2, C4 + 5]);
+    assert_eq!(values(&Chord::roman("Imaj7", 4).unwrap(), C_MAJOR), vec![C4, C4 + 4, C4 + 7, C4 + 11]);
+    assert_eq!(values(&Chord::roman("viiø7", 3).unwrap(), C_MAJOR), vec![C4 - 1, C4 + 2, C4 + 5, C4 + 9]);
+    assert_eq!(values(&Chord::roman("viio7", 3).unwrap(), C_MAJOR), vec![C4 - 1, C4 + 2, C4 + 5, C4 + 8]);
+}
+
+#[test]
+fn test_roman_follows_key() {
+    let d_major = MusicKey { tonic: KeyTonic::D, key_type: KeyType::Major };
+    let a_minor = MusicKey { tonic: KeyTonic::A, key_type: KeyType::Minor };
+
+    assert_eq!(values(&Chord::roman("I", 4).unwrap(), d_major), vec![C4 + 2, C4 + 6, C4 + 9]);
+    // The quality comes from the numeral, not from the key
+    assert_eq!(values(&Chord::roman("i", 4).unwrap(), a_minor), vec![C4 + 9, C4 + 12, C4 + 16]);
+    assert_eq!(values(&Chord::roman("V", 4).unwrap(), a_minor), vec![C4 + 16, C4 + 20, C4 + 23]);
+}
+
+#[test]
+fn test_secondary_chords() {
+    // V/V in C major is D major, V7/ii is A7
+    assert_eq!(values(&Chord::roman("V/V", 3).unwrap(), C_MAJOR), vec![C4 - 10, C4 - 6, C4 - 3]);
+    assert_eq!(values(&Chord::roman("V7/ii", 3).unwrap(), C_MAJOR), vec![C4 - 3, C4 + 1, C4 + 4, C4 + 7]);
+    assert_eq!(values(&Chord::roman("viio/V", 3).unwrap(), C_MAJOR), vec![C4 - 6, C4 - 3, C4]);
+}
+
+#[test]
+fn test_secondary_chord_octave() {
+    // The root is in the same octave as the numeral with the same root
+    let root = |numeral: &str| values(&Chord::roman(numeral, 4).unwrap(), C_MAJOR)[0];
+
+    assert_eq!(root("V/V"), root("II"));
+    assert_eq!(root("V7/IV"), root("I"));
+    assert_eq!(root("V/vi"), root("III"));
+    assert_eq!(root("IV/IV"), root("bVII"));
+}
+
+#[test]
+fn test_invalid_roman() {
+    assert!(Chord::roman("", 4).is_err());
+    assert!(Chord::roman("X", 4).is_err());
+    assert!(Chord::roman("Ix", 4).is_err());
+    assert!(Chord::roman("V/", 4).is_err());
+}
+
+#[test]
+fn test_symbols() {
+    assert_eq!(values(&Chord::symbol("C", 4).unwrap(), C_MAJOR), vec![C4, C4 + 4, C4 + 7]);
+    assert_eq!(values(&Chord::symbol("Cmaj7", 4).unwrap(), C_MAJOR), vec![C4, C4 + 4, C4 + 7, C4 + 11]);
+    assert_eq!(values(&Chord::symbol("F#m7b5", 4).unwrap(), C_MAJOR), vec![C4 + 6, C4 + 9, C4 + 12, C4 + 16]);
+    assert_eq!(values(&Chord::symbol("Bbm", 3).unwrap(), C_MAJOR), vec![C4 - 2, C4 + 1, C4 + 5]);
+    assert_eq!(values(&Chord::symbol("Ddim7", 4).unwrap(), C_MAJOR), vec![C4 + 2, C4 + 5, C4 + 8, C4 + 11]);
+    assert_eq!(values(&Chord::symbol("Eadd9", 4).unwrap(), C_MAJOR), vec![C4 + 4, C4 + 8, C4 + 11, C4 + 18]);
+}
+
+#[test]
+fn test_symbols_ignore_key() {
+    let e_minor = MusicKey { tonic: KeyTonic::E, key_type: KeyType::Minor };
+
+    assert_eq!(values(&Chord::symbol("Cmaj7", 4).unwrap(), e_minor), vec![C4, C4 + 4, C4 + 7, C4 + 11]);
+}
+
+#[test]
+fn test_slash_chords() {
+    // The bass is placed below the chord
+    assert_eq!(values(&Chord::symbol("Gsus4/D", 4).unwrap(), C_MAJOR), vec![C4 + 2, C4 + 7, C4 + 12, C4 + 14]);
+    assert_eq!(values(&Chord::symbol("C/E", 4).unwrap(), C_MAJOR), vec![C4 - 8, C4, C4 + 4, C4 + 7]);
+    assert_eq!(values(&Chord::symbol("C/C", 4).unwrap(), C_MAJOR), vec![C4 - 12, C4, C4 + 4, C4 + 7]);
+}
+
+#[test]
+fn test_invalid_symbols() {
+    assert!(Chord::symbol("", 4).is_err());
+    assert!(Chord::symbol("H", 4).is_err());
+    assert!(Chord::symbol("Cxyz", 4).is_err());
+    assert!(Chord::symbol("C/", 4).is_err());
+    assert!(Chord::symbol("C/H", 4).is_err());
+    assert!(Chord::symbol("C/E7", 4).is_err());
+}
+
+#[test]
+fn test_inversions() {
+    let chord = Chord::symbol("C", 4).unwrap();
+
+    assert_eq!(values(&chord.clone().set_inversion(1), C_MAJOR), vec![C4 + 4, C4 + 7, C4 + 12]);
+    assert_eq!(values(&chord.clone().set_inversion(2), C_MAJOR), vec![C4 + 7, C4 + 12, C4 + 16]);
+    assert_eq!(values(&chord.set_inversion(3), C_MAJOR), vec![C4 + 12, C4 + 16, C4 + 19]);
+}
+
+#[test]
+fn test_voicings() {
+    let chord = Chord::symbol("Cmaj7", 4).unwrap();
+
+    assert_eq!(values(&chord.clone().set_voicing(Voicing::Open), C_MAJOR), vec![C4, C4 + 7, C4 + 16, C4 + 23]);
+    assert_eq!(values(&chord.clone().set_voicing(Voicing::Drop2), C_MAJOR), vec![C4 - 5, C4, C4 + 4, C4 + 11]);
+
+    let triad = Chord::symbol("C", 4).unwrap();
+    assert_eq!(values(&triad.set_voicing(Voicing::Open), C_MAJOR), vec![C4, C4 + 7, C4 + 16]);
+}
+
+#[test]
+fn test_inversion_with_voicing() {
+    let chord = Chord::symbol("G7", 3).unwrap()
+        .set_inversion(1)
+        .set_voicing(Voicing::Drop2);
+
+    // First inversion B D F G, then F is dropped
+    assert_eq!(values(&chord, C_MAJOR), vec![C4 - 7, C4 - 1, C4 + 2, C4 + 7]);
+}
+
+#[test]
+fn test_place() {
+    let mut track = UnboundTrack::new(SineGenerator);
+    let chord = Chord::symbol("Am", 4).unwrap();
+
+    chord.place(&mut track, QUARTER);
+
+    let export = track.convert_to_export_track(SectionInfo {
+        bpm: 120.0,
+        key: C_MAJOR,
+        settings: &SETTINGS,
+    });
+
+    let values: Vec<i32> = export.tones[0].concrete_values.iter().map(|value| value.0).collect();
+    assert_eq!(values, vec![0, 3, 7]);
+}
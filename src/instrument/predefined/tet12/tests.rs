@@ -17,36 +17,43 @@ fn test_placement() {
         index: 0,
         octave: 1,
         offset: 0,
+        absolute: false,
     };
     let second_expected = TET12ScaledTone {
         index: 1,
         octave: 2,
         offset: 0,
+        absolute: false,
     };
     let third_expected = TET12ScaledTone {
         index: 2,
         octave: 3,
         offset: 0,
+        absolute: false,
     };
     let fourth_expected = TET12ScaledTone {
         index: 3,
         octave: 4,
         offset: 0,
+        absolute: false,
     };
     let fifth_expected = TET12ScaledTone {
         index: 4,
         octave: 5,
         offset: 0,
+        absolute: false,
     };
     let sixth_expected = TET12ScaledTone {
         index: 5,
         octave: 6,
         offset: 0,
+        absolute: false,
     };
     let seventh_expected = TET12ScaledTone {
         index: 6,
         octave: 7,
         offset: 0,
+        absolute: false,
     };
 
     let suboctave = second(-2);
@@ -55,6 +62,7 @@ fn test_placement() {
         index: 1,
         octave: -2,
         offset: 0,
+        absolute: false,
     };
 
     assert_eq!(first_note, first_expected);
@@ -81,30 +89,35 @@ fn test_offset() {
         index: 0,
         octave: 3,
         offset: 1,
+        absolute: false,
     });
 
     assert_eq!(note_flat, TET12ScaledTone {
         index: 1,
         octave: 1,
         offset: -1,
+        absolute: false,
     });
 
     assert_eq!(note_neutral, TET12ScaledTone {
         index: 4,
         octave: 2,
         offset: 0,
+        absolute: false,
     });
 
     assert_eq!(note_double_sharp, TET12ScaledTone {
         index: 6,
         octave: 5,
         offset: 2,
+        absolute: false,
     });
 
     assert_eq!(note_double_flat, TET12ScaledTone {
         index: 6,
         octave: 5,
         offset: -2,
+        absolute: false,
     });
 }
 
@@ -221,6 +234,58 @@ fn test_conversion_a_major() {
     assert_eq!(g5, TET12ConcreteTone(10));
 }
 
+#[test]
+fn test_conversion_modes() {
+    use crate::prelude::{MusicKey, KeyTonic, KeyType};
+
+    let d_dorian = MusicKey { tonic: KeyTonic::D, key_type: KeyType::Dorian };
+    let f_lydian = MusicKey { tonic: KeyTonic::F, key_type: KeyType::Lydian };
+    let a_harmonic = MusicKey { tonic: KeyTonic::A, key_type: KeyType::HarmonicMinor };
+
+    // D dorian has the notes of C major
+    let d_dorian_scale: Vec<i32> = [first(4), second(4), third(4), fourth(4), fifth(4), sixth(4), seventh(4)]
+        .iter()
+        .map(|tone| tone.to_concrete_value(d_dorian).0)
+        .collect();
+    assert_eq!(d_dorian_scale, vec![-7, -5, -4, -2, 0, 2, 3]);
+
+    // Lydian has a raised fourth
+    assert_eq!(fourth(4).to_concrete_value(f_lydian), TET12ConcreteTone(-4 + 6));
+
+    // Harmonic minor has a raised seventh
+    assert_eq!(seventh(4).to_concrete_value(a_harmonic), TET12ConcreteTone(11));
+}
+
+#[test]
+fn test_conversion_small_scales() {
+    use crate::prelude::{MusicKey, KeyTonic, KeyType};
+
+    let c_pentatonic = MusicKey { tonic: KeyTonic::C, key_type: KeyType::PentatonicMajor };
+    let a_blues = MusicKey { tonic: KeyTonic::A, key_type: KeyType::Blues };
+
+    // Degrees beyond the scale continue in the next octave
+    assert_eq!(fifth(4).to_concrete_value(c_pentatonic), TET12ConcreteTone(-9 + 9));
+    assert_eq!(sixth(4).to_concrete_value(c_pentatonic), TET12ConcreteTone(-9 + 12));
+    assert_eq!(seventh(4).to_concrete_value(c_pentatonic), TET12ConcreteTone(-9 + 14));
+
+    assert_eq!(fourth(4).to_concrete_value(a_blues), TET12ConcreteTone(6));
+    assert_eq!(seventh(4).to_concrete_value(a_blues), TET12ConcreteTone(12));
+}
+
+#[test]
+fn test_conversion_custom_scale() {
+    use crate::prelude::{MusicKey, KeyTonic, KeyType};
+
+    // Octatonic (half-whole) scale with eight degrees
+    const OCTATONIC: KeyType = KeyType::Custom(&[0, 1, 3, 4, 6, 7, 9, 10]);
+    let key = MusicKey { tonic: KeyTonic::C, key_type: OCTATONIC };
+
+    assert_eq!(OCTATONIC.degrees(), 8);
+    assert_eq!(degree(8, 4).to_concrete_value(key), TET12ConcreteTone(-9 + 10));
+    assert_eq!(degree(9, 4).to_concrete_value(key), TET12ConcreteTone(-9 + 12));
+    assert_eq!(degree(1, 4), first(4));
+}
+
 #[test]
 fn test_frequency_conversion() {
     let key = crate::prelude::music_key::A_MINOR;
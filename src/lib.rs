@@ -222,6 +222,11 @@ traditional music this is called "crescendo" for becoming louder or
 "decrescendo" for becoming quieter. This crate does not differentiate becoming
 louder or becoming quieter.
 
+Use `start_dynamic_change_with` to pass a `DynamicsCurve`, which sets the
+shape of the transition. A linear change in amplitude does not sound even, as
+loudness is perceived logarithmically; use `DynamicsCurve::Logarithmic` for a
+transition that sounds even.
+
 ## Implementing instruments
 
 Instruments represent the entire sound synthesis part of this crate. Here, most
@@ -5,17 +5,35 @@ pub use composer::{
     music_key,
     note::length,
     Note,
+    Articulation,
     ScaledValue,
 
     TimeSignature,
 
+    parameter,
+    Parameter,
+    ParameterMap,
+
+    dynamics,
+    DynamicsCurve,
+    Dynamic,
+    Accent,
+    DynamicsTable,
+
+    Groove,
+    GrooveStep,
+    Humanize,
+    Arpeggiator,
+    ArpeggioPattern,
+    StepPattern,
+
     MusicTrack,
     UnboundTrack,
     measure_track::*,
 };
 pub use music_key::{MusicKey, KeyTonic, KeyType};
 
-pub use crate::{notes, sequential_notes, section, composition};
+pub use crate::{notes, sequential_notes, section, composition, instrument_set};
 
 // Instrument imports
 pub use crate::instrument;
@@ -25,11 +43,26 @@ pub use instrument::{
     noise,
     eq,
     curve,
+    effects,
+    combinators,
 };
 pub use predefined::tet12;
 pub use tet12::{TET12ScaledTone, TET12ConcreteTone};
 pub use curve::{Curve, ScaleType};
 pub use curve::linear_curve::LinearCurve;
+pub use combinators::{Transpose, Unison, Layer, Octaver};
+pub use effects::{
+    Effect,
+    Reverb,
+    Convolution,
+    Delay,
+    Compressor,
+    Limiter,
+    Waveshaper,
+    Bitcrusher,
+    ModulatedDelay,
+    Phaser,
+};
 
 
 // File-export imports
@@ -37,12 +70,14 @@ pub use crate::file_export;
 pub use file_export::{
     FileExport,
     wav_export::WavExport,
+    wav_import::WavImport,
     export_info
 };
 
 pub use export_info::{
     Tone,
     SoundBuffer,
+    StereoBuffer,
     CompositionSettings,
     SectionInfo,
 };